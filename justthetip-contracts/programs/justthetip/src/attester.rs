@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, ErrorCode};
+
+/// Maximum number of registered attesters
+pub const MAX_ATTESTERS: usize = 10;
+
+/// Admin creates the attester registry
+pub(crate) fn initialize_attester_registry(ctx: Context<InitializeAttesterRegistry>) -> Result<()> {
+    let registry = &mut ctx.accounts.registry;
+    registry.attesters = Vec::new();
+    registry.bump = ctx.bumps.registry;
+    Ok(())
+}
+
+/// Admin registers an attester, changes its weight, or removes it with a
+/// weight of zero
+pub(crate) fn set_attester(ctx: Context<ManageAttesters>, attester: Pubkey, weight: u16) -> Result<()> {
+    set_attester_weight(&mut ctx.accounts.registry, attester, weight)
+}
+
+/// Set `attester`'s weight on `registry`. Shared with multisig proposals,
+/// which act as the admin once a multisig exists.
+pub(crate) fn set_attester_weight(
+    registry: &mut Account<AttesterRegistry>,
+    attester: Pubkey,
+    weight: u16,
+) -> Result<()> {
+    let position = registry.attesters.iter().position(|a| a.key == attester);
+    match (position, weight) {
+        (Some(i), 0) => {
+            registry.attesters.remove(i);
+        }
+        (Some(i), _) => registry.attesters[i].weight = weight,
+        (None, 0) => return err!(ErrorCode::NotAnAttester),
+        (None, _) => {
+            require!(
+                registry.attesters.len() < MAX_ATTESTERS,
+                ErrorCode::TooManyAttesters
+            );
+            registry.attesters.push(AttesterWeight { key: attester, weight });
+        }
+    }
+
+    emit!(AttesterUpdated {
+        attester,
+        weight,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct InitializeAttesterRegistry<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AttesterRegistry::INIT_SPACE,
+        seeds = [b"attesters"],
+        bump
+    )]
+    pub registry: Account<'info, AttesterRegistry>,
+
+    #[account(
+        has_one = admin @ ErrorCode::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageAttesters<'info> {
+    #[account(mut, seeds = [b"attesters"], bump = registry.bump)]
+    pub registry: Account<'info, AttesterRegistry>,
+
+    #[account(
+        has_one = admin @ ErrorCode::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct AttesterRegistry {
+    #[max_len(10)]
+    pub attesters: Vec<AttesterWeight>, // 4 + 10 * 34
+    pub bump: u8,                     // 1
+}
+
+impl AttesterRegistry {
+    /// Weight of `key`, or None if it isn't a registered attester
+    pub fn weight_of(&self, key: &Pubkey) -> Option<u16> {
+        self.attesters.iter().find(|a| a.key == *key).map(|a| a.weight)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct AttesterWeight {
+    pub key: Pubkey,
+    pub weight: u16,
+}
+
+// Events
+
+#[event]
+pub struct AttesterUpdated {
+    pub attester: Pubkey,
+    pub weight: u16,
+    pub timestamp: i64,
+}
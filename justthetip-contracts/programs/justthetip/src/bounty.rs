@@ -0,0 +1,341 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, ErrorCode, UserAccount};
+
+/// Escrow a bounty. `approver` (typically a moderator) decides who gets paid;
+/// without one the creator approves their own bounty.
+pub(crate) fn create_bounty(
+    ctx: Context<CreateBounty>,
+    bounty_id: u64,
+    amount: u64,
+    deadline: i64,
+    approver: Option<Pubkey>,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(deadline > now, ErrorCode::InvalidDeadline);
+
+    let bounty = &mut ctx.accounts.bounty;
+    bounty.creator = ctx.accounts.creator.key();
+    bounty.approver = approver.unwrap_or(bounty.creator);
+    bounty.bounty_id = bounty_id;
+    bounty.amount = amount;
+    bounty.deadline = deadline;
+    bounty.created_at = now;
+    bounty.bump = ctx.bumps.bounty;
+
+    match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.creator_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(creator_token_account), Some(token_program)) => {
+            bounty.mint = Some(mint.key());
+
+            let cpi_accounts = Transfer {
+                from: creator_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)?;
+        }
+        (None, None, None, _) => {
+            bounty.mint = None;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.bounty.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    let bounty = &ctx.accounts.bounty;
+    emit!(BountyCreated {
+        bounty: bounty.key(),
+        creator: bounty.creator,
+        approver: bounty.approver,
+        mint: bounty.mint,
+        amount,
+        deadline,
+    });
+
+    Ok(())
+}
+
+/// Approver pays the bounty to the authority of `recipient_account`
+pub(crate) fn release_bounty(ctx: Context<ReleaseBounty>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let bounty = &ctx.accounts.bounty;
+    require!(now < bounty.deadline, ErrorCode::BountyExpired);
+
+    pay_out(
+        bounty,
+        &ctx.accounts.recipient.to_account_info(),
+        &ctx.accounts.creator.to_account_info(),
+        ctx.accounts.vault.as_ref(),
+        ctx.accounts.destination_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    let recipient_account = &mut ctx.accounts.recipient_account;
+    recipient_account.total_received = recipient_account.total_received
+        .checked_add(bounty.amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(BountyReleased {
+        bounty: bounty.key(),
+        approver: ctx.accounts.approver.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount: bounty.amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Return an expired, unreleased bounty to its creator. Anyone may call this.
+pub(crate) fn refund_bounty(ctx: Context<RefundBounty>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let bounty = &ctx.accounts.bounty;
+    require!(now >= bounty.deadline, ErrorCode::BountyNotExpired);
+
+    pay_out(
+        bounty,
+        &ctx.accounts.creator.to_account_info(),
+        &ctx.accounts.creator.to_account_info(),
+        ctx.accounts.vault.as_ref(),
+        ctx.accounts.destination_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    emit!(BountyRefunded {
+        bounty: bounty.key(),
+        creator: bounty.creator,
+        amount: bounty.amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Move the escrowed bounty to `destination`. SPL vaults are emptied and
+/// their rent returned to `rent_destination`.
+fn pay_out<'info>(
+    bounty: &Account<'info, Bounty>,
+    destination: &AccountInfo<'info>,
+    rent_destination: &AccountInfo<'info>,
+    vault: Option<&Account<'info, TokenAccount>>,
+    destination_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+) -> Result<()> {
+    match bounty.mint {
+        Some(mint) => {
+            let (Some(vault), Some(destination_token_account), Some(token_program)) =
+                (vault, destination_token_account, token_program)
+            else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(destination_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(
+                destination_token_account.owner,
+                destination.key(),
+                ErrorCode::AccountMismatch
+            );
+
+            let bounty_id = bounty.bounty_id.to_le_bytes();
+            let seeds = &[
+                b"bounty",
+                bounty.creator.as_ref(),
+                bounty_id.as_ref(),
+                &[bounty.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                vault,
+                destination_token_account,
+                bounty.to_account_info(),
+                signer,
+                token_program,
+                bounty.amount,
+            )?;
+
+            escrow::close_vault(
+                vault,
+                rent_destination.clone(),
+                bounty.to_account_info(),
+                signer,
+                token_program,
+            )
+        }
+        None => {
+            escrow::withdraw_lamports(&bounty.to_account_info(), destination, bounty.amount)
+        }
+    }
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(bounty_id: u64)]
+pub struct CreateBounty<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Bounty::INIT_SPACE,
+        seeds = [b"bounty", creator.key().as_ref(), &bounty_id.to_le_bytes()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// Only for SPL bounties
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL bounties
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"bounty-vault", bounty.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = bounty,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL bounties
+    #[account(mut)]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseBounty<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator @ ErrorCode::AccountMismatch,
+        has_one = approver @ ErrorCode::Unauthorized,
+        seeds = [b"bounty", bounty.creator.as_ref(), &bounty.bounty_id.to_le_bytes()],
+        bump = bounty.bump,
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    pub approver: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+
+    /// CHECK: Receives the bounty; must be the recipient account's authority
+    #[account(mut, address = recipient_account.authority @ ErrorCode::AccountMismatch)]
+    pub recipient: AccountInfo<'info>,
+
+    /// CHECK: Receives the escrow rent; must match the bounty creator
+    #[account(mut)]
+    pub creator: AccountInfo<'info>,
+
+    /// Only for SPL bounties
+    #[account(
+        mut,
+        seeds = [b"bounty-vault", bounty.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL bounties: the recipient's token account
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct RefundBounty<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator @ ErrorCode::AccountMismatch,
+        seeds = [b"bounty", bounty.creator.as_ref(), &bounty.bounty_id.to_le_bytes()],
+        bump = bounty.bump,
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    /// CHECK: Receives the refund; must match the bounty creator
+    #[account(mut)]
+    pub creator: AccountInfo<'info>,
+
+    /// Only for SPL bounties
+    #[account(
+        mut,
+        seeds = [b"bounty-vault", bounty.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL bounties: the creator's token account
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bounty {
+    pub creator: Pubkey,              // 32
+    pub approver: Pubkey,             // 32
+    pub bounty_id: u64,               // 8
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub amount: u64,                  // 8
+    pub deadline: i64,                // 8
+    pub created_at: i64,              // 8
+    pub bump: u8,                     // 1
+}
+
+// Events
+
+#[event]
+pub struct BountyCreated {
+    pub bounty: Pubkey,
+    pub creator: Pubkey,
+    pub approver: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct BountyReleased {
+    pub bounty: Pubkey,
+    pub approver: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BountyRefunded {
+    pub bounty: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,416 @@
+use anchor_lang::prelude::*;
+
+use crate::program::Justthetip;
+use crate::{BadgeTier, ErrorCode, UserAccount, BPS_DENOMINATOR};
+
+/// Highest protocol fee the admin can set (10%)
+pub const MAX_FEE_BPS: u16 = 1_000;
+/// Minimum time between queueing a config change and applying it (2 days)
+pub const CONFIG_CHANGE_DELAY: i64 = 2 * 24 * 60 * 60;
+/// Maximum number of SPL mints with their own tip minimum
+pub const MAX_MINT_MINIMUMS: usize = 8;
+/// Maximum number of badge tier thresholds, one per tier above Unranked
+pub const MAX_TIER_THRESHOLDS: usize = 5;
+
+/// Create the program config. Only the program's upgrade authority can do
+/// this, and it becomes the config admin.
+pub(crate) fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+    params.validate()?;
+
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.admin.key();
+    config.bump = ctx.bumps.config;
+    params.apply(config);
+
+    msg!("Config initialized with admin {}", config.admin);
+    Ok(())
+}
+
+/// Admin queues a change to the config. It can be applied once
+/// `CONFIG_CHANGE_DELAY` has passed.
+pub(crate) fn queue_config_change(ctx: Context<ManageConfig>, params: ConfigParams) -> Result<()> {
+    queue_change(&mut ctx.accounts.config, params, ctx.accounts.admin.key())
+}
+
+/// Admin drops the queued change
+pub(crate) fn cancel_config_change(ctx: Context<ManageConfig>) -> Result<()> {
+    cancel_change(&mut ctx.accounts.config, ctx.accounts.admin.key())
+}
+
+/// Apply the queued change once its delay has passed. Anyone may call this.
+pub(crate) fn apply_config_change(ctx: Context<ApplyConfigChange>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.config;
+    let pending = config
+        .pending_change
+        .take()
+        .ok_or(ErrorCode::NoPendingConfigChange)?;
+    require!(now >= pending.eta, ErrorCode::ConfigChangeNotReady);
+    pending.params.apply(config);
+
+    emit!(ConfigChangeApplied {
+        config: config.key(),
+        params: pending.params,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Queue `params` on `config`. Shared with multisig proposals, which act as
+/// the admin once a multisig exists.
+pub(crate) fn queue_change(
+    config: &mut Account<Config>,
+    params: ConfigParams,
+    queued_by: Pubkey,
+) -> Result<()> {
+    params.validate()?;
+    require!(
+        config.pending_change.is_none(),
+        ErrorCode::ConfigChangePending
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let eta = now
+        .checked_add(CONFIG_CHANGE_DELAY)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    config.pending_change = Some(PendingConfigChange {
+        params: params.clone(),
+        queued_at: now,
+        eta,
+    });
+
+    emit!(ConfigChangeQueued {
+        config: config.key(),
+        queued_by,
+        params,
+        eta,
+    });
+
+    Ok(())
+}
+
+/// Drop the change queued on `config`
+pub(crate) fn cancel_change(config: &mut Account<Config>, cancelled_by: Pubkey) -> Result<()> {
+    let pending = config
+        .pending_change
+        .take()
+        .ok_or(ErrorCode::NoPendingConfigChange)?;
+
+    emit!(ConfigChangeCancelled {
+        config: config.key(),
+        cancelled_by,
+        params: pending.params,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Justthetip>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageConfig<'info> {
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyConfigChange<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,                // 32
+    pub treasury: Pubkey,             // 32
+    pub fee_bps: u16,                 // 2
+    pub referral_share_bps: u16,      // 2
+    pub referral_period: i64,         // 8
+    pub large_airdrop_threshold: u64, // 8 (0 = no multisig approval needed)
+    pub paused: bool,                 // 1
+    pub min_sol_tip: u64,             // 8
+    #[max_len(8)]
+    pub min_token_tips: Vec<MintMinimum>, // 4 + 8 * 48
+    pub rate_limit_tips: u16,         // 2 (0 = no rate limit)
+    pub rate_limit_slots: u64,        // 8
+    pub min_score: u64,               // 8
+    pub max_score: u64,               // 8
+    pub tip_reputation: u64,          // 8 (0 = tips earn no reputation)
+    pub tip_reputation_daily_cap: u64, // 8
+    pub badge_cooldown: i64,          // 8
+    pub neutral_score: u64,           // 8
+    pub score_half_life: i64,         // 8 (0 = scores never decay)
+    pub airdrop_min_score: u64,       // 8 (0 = any wallet can claim)
+    pub large_tip_amount: u64,        // 8 (0 = no large tip gate)
+    pub large_tip_min_score: u64,     // 8
+    #[max_len(5)]
+    pub tier_thresholds: Vec<u64>,    // 4 + 5 * 8 (lowest score for Bronze, Silver, ...)
+    pub pending_change: Option<PendingConfigChange>, // 1 + 599
+    pub bump: u8,                     // 1
+}
+
+impl Config {
+    /// Smallest tip allowed in `mint` (None for SOL). Mints without an entry
+    /// have no minimum beyond 1.
+    pub fn min_tip(&self, mint: Option<Pubkey>) -> u64 {
+        match mint {
+            None => self.min_sol_tip,
+            Some(mint) => self
+                .min_token_tips
+                .iter()
+                .find(|m| m.mint == mint)
+                .map_or(0, |m| m.amount),
+        }
+    }
+
+    /// Reject tips below the minimum for their mint, and count the tip
+    /// against the sender's rate limit window.
+    pub fn check_tip(
+        &self,
+        sender_account: &mut UserAccount,
+        mint: Option<Pubkey>,
+        amount: u64,
+        slot: u64,
+    ) -> Result<()> {
+        require!(amount >= self.min_tip(mint), ErrorCode::TipBelowMinimum);
+
+        if self.rate_limit_tips == 0 {
+            return Ok(());
+        }
+        if slot >= sender_account.rate_window_start.saturating_add(self.rate_limit_slots) {
+            sender_account.rate_window_start = slot;
+            sender_account.rate_window_tips = 0;
+        }
+        require!(
+            sender_account.rate_window_tips < self.rate_limit_tips,
+            ErrorCode::TipRateLimited
+        );
+        sender_account.rate_window_tips += 1;
+        Ok(())
+    }
+
+    /// Trust score a sender needs for a tip of `amount` in `mint` (None for
+    /// SOL). SOL tips from `large_tip_amount` up, and SPL tips from their
+    /// mint's `large_amount` up, need `large_tip_min_score`.
+    pub fn tip_min_score(&self, mint: Option<Pubkey>, amount: u64) -> u64 {
+        let large_amount = match mint {
+            None => self.large_tip_amount,
+            Some(mint) => self
+                .min_token_tips
+                .iter()
+                .find(|m| m.mint == mint)
+                .map_or(0, |m| m.large_amount),
+        };
+        if large_amount > 0 && amount >= large_amount {
+            self.large_tip_min_score
+        } else {
+            0
+        }
+    }
+
+    /// Trust score a sender needs for an NFT tip. An NFT's value can't be
+    /// read on chain, so every NFT tip counts as large while large SOL tips
+    /// are gated.
+    pub fn nft_tip_min_score(&self) -> u64 {
+        self.tip_min_score(None, self.large_tip_amount)
+    }
+
+    /// Badge tier for `score`: the highest tier whose threshold it reaches
+    pub fn tier_for(&self, score: u64) -> BadgeTier {
+        let reached = self.tier_thresholds.iter().filter(|t| score >= **t).count();
+        reached
+            .checked_sub(1)
+            .map_or(BadgeTier::Unranked, |i| BadgeTier::RANKED[i])
+    }
+
+    /// Protocol fee taken out of a tip of `amount`
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// Part of `fee` owed to the sender's referrer at `now`. Zero once the
+    /// referral period has run out.
+    pub fn referral_share(&self, sender: &UserAccount, fee: u64, now: i64) -> u64 {
+        if sender.referrer.is_none()
+            || now >= sender.referred_at.saturating_add(self.referral_period)
+        {
+            return 0;
+        }
+        (fee as u128 * self.referral_share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+}
+
+/// Admin-settable config values
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct ConfigParams {
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub referral_period: i64,
+    pub large_airdrop_threshold: u64,
+    pub paused: bool,
+    pub min_sol_tip: u64,
+    #[max_len(8)]
+    pub min_token_tips: Vec<MintMinimum>,
+    pub rate_limit_tips: u16,
+    pub rate_limit_slots: u64,
+    pub min_score: u64,
+    pub max_score: u64,
+    pub tip_reputation: u64,
+    pub tip_reputation_daily_cap: u64,
+    pub badge_cooldown: i64,
+    pub neutral_score: u64,
+    pub score_half_life: i64,
+    pub airdrop_min_score: u64,
+    pub large_tip_amount: u64,
+    pub large_tip_min_score: u64,
+    #[max_len(5)]
+    pub tier_thresholds: Vec<u64>,
+}
+
+/// Tip limits for one SPL mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct MintMinimum {
+    pub mint: Pubkey,
+    /// Smallest tip
+    pub amount: u64,
+    /// Tips this large need `large_tip_min_score` (0 = no gate)
+    pub large_amount: u64,
+}
+
+/// A config change waiting out its delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PendingConfigChange {
+    pub params: ConfigParams,
+    pub queued_at: i64,
+    pub eta: i64,
+}
+
+impl ConfigParams {
+    pub(crate) fn validate(&self) -> Result<()> {
+        require!(self.fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+        require!(
+            self.referral_share_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::InvalidFee
+        );
+        require!(self.referral_period > 0, ErrorCode::InvalidInterval);
+        require!(
+            self.min_token_tips.len() <= MAX_MINT_MINIMUMS,
+            ErrorCode::InvalidTipLimits
+        );
+        for (i, minimum) in self.min_token_tips.iter().enumerate() {
+            require!(
+                !self.min_token_tips[..i].iter().any(|m| m.mint == minimum.mint),
+                ErrorCode::InvalidTipLimits
+            );
+        }
+        require!(
+            self.rate_limit_tips == 0 || self.rate_limit_slots > 0,
+            ErrorCode::InvalidTipLimits
+        );
+        require!(self.min_score <= self.max_score, ErrorCode::InvalidScoreRange);
+        require!(
+            self.tip_reputation == 0 || self.tip_reputation_daily_cap > 0,
+            ErrorCode::InvalidReputationSettings
+        );
+        require!(self.badge_cooldown >= 0, ErrorCode::InvalidInterval);
+        require!(
+            (self.min_score..=self.max_score).contains(&self.neutral_score),
+            ErrorCode::InvalidScoreRange
+        );
+        require!(self.score_half_life >= 0, ErrorCode::InvalidInterval);
+        require!(
+            self.tier_thresholds.len() <= MAX_TIER_THRESHOLDS
+                && self.tier_thresholds.windows(2).all(|w| w[0] < w[1])
+                && self.tier_thresholds.iter().all(|t| *t <= self.max_score),
+            ErrorCode::InvalidTierThresholds
+        );
+        Ok(())
+    }
+
+    pub(crate) fn apply(&self, config: &mut Config) {
+        config.treasury = self.treasury;
+        config.fee_bps = self.fee_bps;
+        config.referral_share_bps = self.referral_share_bps;
+        config.referral_period = self.referral_period;
+        config.large_airdrop_threshold = self.large_airdrop_threshold;
+        config.paused = self.paused;
+        config.min_sol_tip = self.min_sol_tip;
+        config.min_token_tips = self.min_token_tips.clone();
+        config.rate_limit_tips = self.rate_limit_tips;
+        config.rate_limit_slots = self.rate_limit_slots;
+        config.min_score = self.min_score;
+        config.max_score = self.max_score;
+        config.tip_reputation = self.tip_reputation;
+        config.tip_reputation_daily_cap = self.tip_reputation_daily_cap;
+        config.badge_cooldown = self.badge_cooldown;
+        config.neutral_score = self.neutral_score;
+        config.score_half_life = self.score_half_life;
+        config.airdrop_min_score = self.airdrop_min_score;
+        config.large_tip_amount = self.large_tip_amount;
+        config.large_tip_min_score = self.large_tip_min_score;
+        config.tier_thresholds = self.tier_thresholds.clone();
+    }
+}
+
+// Events
+
+#[event]
+pub struct ConfigChangeQueued {
+    pub config: Pubkey,
+    pub queued_by: Pubkey,
+    pub params: ConfigParams,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ConfigChangeApplied {
+    pub config: Pubkey,
+    pub params: ConfigParams,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub config: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub params: ConfigParams,
+    pub timestamp: i64,
+}
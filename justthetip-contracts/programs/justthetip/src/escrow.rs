@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
+
+use crate::ErrorCode;
+
+/// Move `amount` lamports out of a program-owned escrow account. Accounts
+/// that carry data can't be debited through the System program, so the
+/// lamports are moved directly. The escrow has to stay rent-exempt; escrows
+/// that are done with are closed by their instruction afterwards.
+pub(crate) fn withdraw_lamports(
+    escrow: &AccountInfo,
+    destination: &AccountInfo,
+    amount: u64,
+) -> Result<()> {
+    let rent_exempt = Rent::get()?.minimum_balance(escrow.data_len());
+    require!(
+        escrow.lamports().saturating_sub(rent_exempt) >= amount,
+        ErrorCode::InsufficientEscrowFunds
+    );
+
+    **escrow.try_borrow_mut_lamports()? -= amount;
+    **destination.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+/// Transfer `amount` tokens out of an escrow's vault, signed by the escrow PDA
+pub(crate) fn withdraw_tokens<'info>(
+    vault: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    escrow: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: vault.to_account_info(),
+        to: destination.to_account_info(),
+        authority: escrow,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Close an emptied escrow vault, returning its rent to `rent_destination`
+pub(crate) fn close_vault<'info>(
+    vault: &Account<'info, TokenAccount>,
+    rent_destination: AccountInfo<'info>,
+    escrow: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: rent_destination,
+        authority: escrow,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::close_account(cpi_ctx)
+}
@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+
+use crate::{escrow, guild_treasury, ErrorCode, TipSol, TipSplToken, UserAccount};
+
+/// Pay the protocol fee on a SOL tip from the sender's wallet, or from the
+/// guild treasury paying the tip. The referrer's share is credited to their
+/// user account PDA, to be withdrawn later.
+pub(crate) fn collect_sol_fee(accounts: &mut TipSol, fee: u64, now: i64) -> Result<()> {
+    let share = accounts.config.referral_share(&accounts.sender_account, fee, now);
+    let to_treasury = fee - share;
+
+    if to_treasury > 0 {
+        guild_treasury::pay_sol(
+            accounts.guild_treasury.as_deref(),
+            &accounts.sender,
+            &accounts.system_program,
+            &accounts.treasury,
+            to_treasury,
+        )?;
+    }
+
+    if share > 0 {
+        let referrer_account =
+            referrer_for(&accounts.sender_account, accounts.referrer_account.as_deref_mut())?;
+
+        guild_treasury::pay_sol(
+            accounts.guild_treasury.as_deref(),
+            &accounts.sender,
+            &accounts.system_program,
+            &referrer_account.to_account_info(),
+            share,
+        )?;
+        record_referral(referrer_account, &accounts.sender_account, share)?;
+    }
+
+    Ok(())
+}
+
+/// Pay the protocol fee on an SPL tip from the sender's token account (the
+/// guild vault for guild tips). The referrer's share goes straight to their
+/// token account.
+pub(crate) fn collect_spl_fee(accounts: &mut TipSplToken, fee: u64, now: i64) -> Result<()> {
+    let share = accounts.config.referral_share(&accounts.sender_account, fee, now);
+    let to_treasury = fee - share;
+    let mint = accounts.sender_token_account.mint;
+
+    if to_treasury > 0 {
+        let treasury_token_account = accounts
+            .treasury_token_account
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccounts)?;
+        require_keys_eq!(treasury_token_account.mint, mint, ErrorCode::MintMismatch);
+        require_keys_eq!(
+            treasury_token_account.owner,
+            accounts.config.treasury,
+            ErrorCode::AccountMismatch
+        );
+
+        guild_treasury::pay_spl(
+            accounts.guild_treasury.as_deref(),
+            &accounts.sender,
+            &accounts.sender_token_account,
+            treasury_token_account,
+            &accounts.token_program,
+            to_treasury,
+        )?;
+    }
+
+    if share > 0 {
+        let referrer_account =
+            referrer_for(&accounts.sender_account, accounts.referrer_account.as_deref_mut())?;
+        let referrer_token_account = accounts
+            .referrer_token_account
+            .as_ref()
+            .ok_or(ErrorCode::MissingTokenAccounts)?;
+        require_keys_eq!(referrer_token_account.mint, mint, ErrorCode::MintMismatch);
+        require_keys_eq!(
+            referrer_token_account.owner,
+            referrer_account.authority,
+            ErrorCode::AccountMismatch
+        );
+
+        guild_treasury::pay_spl(
+            accounts.guild_treasury.as_deref(),
+            &accounts.sender,
+            &accounts.sender_token_account,
+            referrer_token_account,
+            &accounts.token_program,
+            share,
+        )?;
+        record_referral(referrer_account, &accounts.sender_account, share)?;
+    }
+
+    Ok(())
+}
+
+/// Referrer withdraws the SOL referral earnings held in their user account.
+/// Only recorded earnings are paid out, never lamports sent to the PDA otherwise.
+pub(crate) fn withdraw_referral_earnings(ctx: Context<WithdrawReferralEarnings>) -> Result<()> {
+    let user_info = ctx.accounts.user_account.to_account_info();
+    let rent_exempt = Rent::get()?.minimum_balance(user_info.data_len());
+    let excess = user_info.lamports().saturating_sub(rent_exempt);
+
+    let user_account = &mut ctx.accounts.user_account;
+    let available = user_account.referral_earnings.min(excess);
+    require!(available > 0, ErrorCode::NothingToWithdraw);
+
+    user_account.referral_earnings -= available;
+    escrow::withdraw_lamports(&user_info, &ctx.accounts.authority, available)?;
+
+    msg!("Referral earnings withdrawn: {} lamports", available);
+    Ok(())
+}
+
+fn referrer_for<'a, 'info>(
+    sender_account: &UserAccount,
+    referrer_account: Option<&'a mut Account<'info, UserAccount>>,
+) -> Result<&'a mut Account<'info, UserAccount>> {
+    let referrer_account = referrer_account.ok_or(ErrorCode::MissingReferrer)?;
+    require!(
+        sender_account.referrer == Some(referrer_account.key()),
+        ErrorCode::MissingReferrer
+    );
+    Ok(referrer_account)
+}
+
+fn record_referral(
+    referrer_account: &mut Account<UserAccount>,
+    sender_account: &Account<UserAccount>,
+    share: u64,
+) -> Result<()> {
+    referrer_account.referral_earnings = referrer_account.referral_earnings
+        .checked_add(share)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(ReferralRewardEvent {
+        referrer: referrer_account.key(),
+        referred: sender_account.key(),
+        amount: share,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct WithdrawReferralEarnings<'info> {
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"user", user_account.discord_id.as_bytes()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+// Events
+
+#[event]
+pub struct ReferralRewardEvent {
+    pub referrer: Pubkey,
+    pub referred: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,613 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::slot_hashes::MAX_ENTRIES;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, ErrorCode};
+
+/// Most entrants a single giveaway can hold
+pub const MAX_GIVEAWAY_ENTRANTS: usize = 200;
+/// Most winners a single giveaway can draw
+pub const MAX_GIVEAWAY_WINNERS: usize = 25;
+
+/// Host commits to `hash(secret)` and escrows `prize_per_winner` for each
+/// winner. Entries close at `draw_slot`, whose slot hash seeds the draw.
+pub(crate) fn create_giveaway(
+    ctx: Context<CreateGiveaway>,
+    giveaway_id: u64,
+    commitment: [u8; 32],
+    prize_per_winner: u64,
+    winners_count: u8,
+    draw_slot: u64,
+    claim_deadline: i64,
+) -> Result<()> {
+    require!(prize_per_winner > 0, ErrorCode::InvalidAmount);
+    require!(
+        winners_count > 0 && winners_count as usize <= MAX_GIVEAWAY_WINNERS,
+        ErrorCode::TooManyWinners
+    );
+    let clock = Clock::get()?;
+    require!(draw_slot > clock.slot, ErrorCode::InvalidDeadline);
+    require!(claim_deadline > clock.unix_timestamp, ErrorCode::InvalidDeadline);
+
+    let total = prize_per_winner
+        .checked_mul(winners_count as u64)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    let giveaway = &mut ctx.accounts.giveaway;
+    giveaway.host = ctx.accounts.host.key();
+    giveaway.giveaway_id = giveaway_id;
+    giveaway.commitment = commitment;
+    giveaway.prize_per_winner = prize_per_winner;
+    giveaway.winners_count = winners_count;
+    giveaway.draw_slot = draw_slot;
+    giveaway.claim_deadline = claim_deadline;
+    giveaway.revealed = false;
+    giveaway.entrants = Vec::new();
+    giveaway.winners = Vec::new();
+    giveaway.bump = ctx.bumps.giveaway;
+
+    match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.host_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(host_token_account), Some(token_program)) => {
+            giveaway.mint = Some(mint.key());
+
+            let cpi_accounts = Transfer {
+                from: host_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.host.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, total)?;
+        }
+        (None, None, None, _) => {
+            giveaway.mint = None;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.host.to_account_info(),
+                    to: ctx.accounts.giveaway.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, total)?;
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    let giveaway = &ctx.accounts.giveaway;
+    emit!(GiveawayCreated {
+        giveaway: giveaway.key(),
+        host: giveaway.host,
+        commitment,
+        mint: giveaway.mint,
+        prize_per_winner,
+        winners_count,
+        draw_slot,
+    });
+
+    Ok(())
+}
+
+/// Register the signer as an entrant before the draw slot
+pub(crate) fn enter_giveaway(ctx: Context<EnterGiveaway>) -> Result<()> {
+    let giveaway = &mut ctx.accounts.giveaway;
+    let entrant = ctx.accounts.entrant.key();
+
+    require!(Clock::get()?.slot < giveaway.draw_slot, ErrorCode::EntriesClosed);
+    require!(
+        giveaway.entrants.len() < MAX_GIVEAWAY_ENTRANTS,
+        ErrorCode::GiveawayFull
+    );
+    require!(
+        !giveaway.entrants.contains(&entrant),
+        ErrorCode::AlreadyEntered
+    );
+
+    giveaway.entrants.push(entrant);
+
+    emit!(GiveawayEntered {
+        giveaway: giveaway.key(),
+        entrant,
+        entrants: giveaway.entrants.len() as u16,
+    });
+
+    Ok(())
+}
+
+/// Host reveals the secret. The winners are drawn from
+/// `hash(secret, slot_hash(draw_slot), giveaway)`, so neither the host nor
+/// the entrants could know the outcome while entries were open.
+pub(crate) fn reveal_giveaway(ctx: Context<RevealGiveaway>, secret: [u8; 32]) -> Result<()> {
+    let giveaway = &ctx.accounts.giveaway;
+    let clock = Clock::get()?;
+
+    require!(!giveaway.revealed, ErrorCode::AlreadyRevealed);
+    require!(clock.slot > giveaway.draw_slot, ErrorCode::DrawSlotNotReached);
+    require!(
+        clock.unix_timestamp < giveaway.claim_deadline,
+        ErrorCode::ClaimDeadlinePassed
+    );
+    require!(
+        hashv(&[&secret]).to_bytes() == giveaway.commitment,
+        ErrorCode::CommitmentMismatch
+    );
+
+    let slot_hash = find_slot_hash(&ctx.accounts.slot_hashes, giveaway.draw_slot)?;
+    let seed = hashv(&[&secret, &slot_hash, giveaway.key().as_ref()]).to_bytes();
+
+    // Partial Fisher-Yates shuffle so nobody can win twice
+    let mut pool = giveaway.entrants.clone();
+    let draws = (giveaway.winners_count as usize).min(pool.len());
+    let mut winners = Vec::with_capacity(draws);
+    for i in 0..draws {
+        let roll = hashv(&[&seed, &(i as u64).to_le_bytes()]).to_bytes();
+        let index = u64::from_le_bytes(roll[..8].try_into().unwrap()) % pool.len() as u64;
+        winners.push(GiveawayWinner {
+            winner: pool.swap_remove(index as usize),
+            claimed: false,
+        });
+    }
+
+    let giveaway = &mut ctx.accounts.giveaway;
+    giveaway.revealed = true;
+    giveaway.winners = winners;
+
+    emit!(GiveawayRevealed {
+        giveaway: giveaway.key(),
+        secret,
+        slot_hash,
+        winners: giveaway.winners.iter().map(|w| w.winner).collect(),
+    });
+
+    Ok(())
+}
+
+/// A drawn winner claims their prize
+pub(crate) fn claim_giveaway_prize(ctx: Context<ClaimGiveawayPrize>) -> Result<()> {
+    let giveaway = &ctx.accounts.giveaway;
+    require!(giveaway.revealed, ErrorCode::NotRevealed);
+
+    let winner = ctx.accounts.winner.key();
+    let index = giveaway
+        .winners
+        .iter()
+        .position(|w| w.winner == winner)
+        .ok_or(ErrorCode::NotAWinner)?;
+    require!(!giveaway.winners[index].claimed, ErrorCode::PrizeAlreadyClaimed);
+    let amount = giveaway.prize_per_winner;
+
+    match giveaway.mint {
+        Some(mint) => {
+            let (Some(vault), Some(winner_token_account), Some(token_program)) = (
+                &ctx.accounts.vault,
+                &ctx.accounts.winner_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(winner_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(winner_token_account.owner, winner, ErrorCode::AccountMismatch);
+
+            let giveaway_id = giveaway.giveaway_id.to_le_bytes();
+            let seeds = &[
+                b"giveaway",
+                giveaway.host.as_ref(),
+                giveaway_id.as_ref(),
+                &[giveaway.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                vault,
+                winner_token_account,
+                giveaway.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )?;
+        }
+        None => {
+            escrow::withdraw_lamports(&giveaway.to_account_info(), &ctx.accounts.winner, amount)?;
+        }
+    }
+
+    let giveaway = &mut ctx.accounts.giveaway;
+    giveaway.winners[index].claimed = true;
+
+    emit!(GiveawayPrizeClaimed {
+        giveaway: giveaway.key(),
+        winner,
+        amount,
+    });
+
+    Ok(())
+}
+
+/// If the host never revealed by the claim deadline, the prize is forfeited
+/// to the entrants. Each entrant claims an equal share of what is left and
+/// is struck from the entrant list; the last one takes any rounding remainder.
+pub(crate) fn claim_forfeited_giveaway(ctx: Context<ClaimForfeitedGiveaway>) -> Result<()> {
+    let giveaway = &ctx.accounts.giveaway;
+    require!(
+        !giveaway.revealed && Clock::get()?.unix_timestamp >= giveaway.claim_deadline,
+        ErrorCode::GiveawayNotForfeited
+    );
+
+    let entrant = ctx.accounts.entrant.key();
+    let index = giveaway
+        .entrants
+        .iter()
+        .position(|e| *e == entrant)
+        .ok_or(ErrorCode::NotAnEntrant)?;
+    let shares = giveaway.entrants.len() as u64;
+
+    let amount = match giveaway.mint {
+        Some(mint) => {
+            let (Some(vault), Some(entrant_token_account), Some(token_program)) = (
+                &ctx.accounts.vault,
+                &ctx.accounts.entrant_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(entrant_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(entrant_token_account.owner, entrant, ErrorCode::AccountMismatch);
+
+            let giveaway_id = giveaway.giveaway_id.to_le_bytes();
+            let seeds = &[
+                b"giveaway",
+                giveaway.host.as_ref(),
+                giveaway_id.as_ref(),
+                &[giveaway.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let amount = vault.amount / shares;
+            escrow::withdraw_tokens(
+                vault,
+                entrant_token_account,
+                giveaway.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )?;
+            amount
+        }
+        None => {
+            let giveaway = giveaway.to_account_info();
+            let rent_exempt = Rent::get()?.minimum_balance(giveaway.data_len());
+            let amount = giveaway.lamports().saturating_sub(rent_exempt) / shares;
+            escrow::withdraw_lamports(&giveaway, &ctx.accounts.entrant, amount)?;
+            amount
+        }
+    };
+
+    let giveaway = &mut ctx.accounts.giveaway;
+    giveaway.entrants.swap_remove(index);
+
+    emit!(GiveawayForfeitClaimed {
+        giveaway: giveaway.key(),
+        entrant,
+        amount,
+    });
+
+    Ok(())
+}
+
+/// Host closes the giveaway once every prize is claimed or the claim deadline
+/// has passed, taking back any unclaimed or undrawn prizes. A prize the host
+/// never revealed belongs to the entrants, so it can only be closed once they
+/// have all claimed their share.
+pub(crate) fn close_giveaway(ctx: Context<CloseGiveaway>) -> Result<()> {
+    let giveaway = &ctx.accounts.giveaway;
+    let all_claimed = giveaway.revealed && giveaway.winners.iter().all(|w| w.claimed);
+    require!(
+        all_claimed || Clock::get()?.unix_timestamp >= giveaway.claim_deadline,
+        ErrorCode::PoolStillClaimable
+    );
+    require!(
+        giveaway.revealed || giveaway.entrants.is_empty(),
+        ErrorCode::PrizeForfeited
+    );
+
+    if giveaway.mint.is_some() {
+        let (Some(vault), Some(host_token_account), Some(token_program)) = (
+            &ctx.accounts.vault,
+            &ctx.accounts.host_token_account,
+            &ctx.accounts.token_program,
+        ) else {
+            return err!(ErrorCode::MissingTokenAccounts);
+        };
+
+        let giveaway_id = giveaway.giveaway_id.to_le_bytes();
+        let seeds = &[
+            b"giveaway",
+            giveaway.host.as_ref(),
+            giveaway_id.as_ref(),
+            &[giveaway.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        if vault.amount > 0 {
+            escrow::withdraw_tokens(
+                vault,
+                host_token_account,
+                giveaway.to_account_info(),
+                signer,
+                token_program,
+                vault.amount,
+            )?;
+        }
+
+        escrow::close_vault(
+            vault,
+            ctx.accounts.host.to_account_info(),
+            giveaway.to_account_info(),
+            signer,
+            token_program,
+        )?;
+    }
+
+    // SOL left in the giveaway returns with the account rent on close
+    msg!("Giveaway closed by host");
+
+    Ok(())
+}
+
+/// Look up the hash of the first recorded slot at or after `target` in the
+/// SlotHashes sysvar. Entries are stored newest first as (slot, hash).
+fn find_slot_hash(slot_hashes: &AccountInfo, target: u64) -> Result<[u8; 32]> {
+    const ENTRY_LEN: usize = 8 + 32;
+
+    let data = slot_hashes.try_borrow_data()?;
+    let len = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+    let slot_at = |i: usize| {
+        let start = 8 + i * ENTRY_LEN;
+        u64::from_le_bytes(data[start..start + 8].try_into().unwrap())
+    };
+
+    // A full history whose oldest entry is newer than `target` has dropped it
+    require!(len > 0, ErrorCode::SlotHashUnavailable);
+    require!(
+        len < MAX_ENTRIES || slot_at(len - 1) <= target,
+        ErrorCode::SlotHashUnavailable
+    );
+
+    let mut found = None;
+    for i in 0..len {
+        if slot_at(i) < target {
+            break;
+        }
+        found = Some(i);
+    }
+
+    let start = 8 + found.ok_or(ErrorCode::SlotHashUnavailable)? * ENTRY_LEN + 8;
+    Ok(data[start..start + 32].try_into().unwrap())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(giveaway_id: u64)]
+pub struct CreateGiveaway<'info> {
+    #[account(
+        init,
+        payer = host,
+        space = 8 + Giveaway::INIT_SPACE,
+        seeds = [b"giveaway", host.key().as_ref(), &giveaway_id.to_le_bytes()],
+        bump
+    )]
+    pub giveaway: Box<Account<'info, Giveaway>>,
+
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// Only for SPL giveaways
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL giveaways
+    #[account(
+        init,
+        payer = host,
+        seeds = [b"giveaway-vault", giveaway.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = giveaway,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL giveaways
+    #[account(mut)]
+    pub host_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnterGiveaway<'info> {
+    #[account(
+        mut,
+        seeds = [b"giveaway", giveaway.host.as_ref(), &giveaway.giveaway_id.to_le_bytes()],
+        bump = giveaway.bump,
+    )]
+    pub giveaway: Box<Account<'info, Giveaway>>,
+
+    pub entrant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealGiveaway<'info> {
+    #[account(
+        mut,
+        has_one = host @ ErrorCode::Unauthorized,
+        seeds = [b"giveaway", giveaway.host.as_ref(), &giveaway.giveaway_id.to_le_bytes()],
+        bump = giveaway.bump,
+    )]
+    pub giveaway: Box<Account<'info, Giveaway>>,
+
+    pub host: Signer<'info>,
+
+    /// CHECK: Read manually; the sysvar is too large to deserialize on chain
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimGiveawayPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"giveaway", giveaway.host.as_ref(), &giveaway.giveaway_id.to_le_bytes()],
+        bump = giveaway.bump,
+    )]
+    pub giveaway: Box<Account<'info, Giveaway>>,
+
+    #[account(mut)]
+    pub winner: Signer<'info>,
+
+    /// Only for SPL giveaways
+    #[account(
+        mut,
+        seeds = [b"giveaway-vault", giveaway.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL giveaways
+    #[account(mut)]
+    pub winner_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimForfeitedGiveaway<'info> {
+    #[account(
+        mut,
+        seeds = [b"giveaway", giveaway.host.as_ref(), &giveaway.giveaway_id.to_le_bytes()],
+        bump = giveaway.bump,
+    )]
+    pub giveaway: Box<Account<'info, Giveaway>>,
+
+    #[account(mut)]
+    pub entrant: Signer<'info>,
+
+    /// Only for SPL giveaways
+    #[account(
+        mut,
+        seeds = [b"giveaway-vault", giveaway.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL giveaways
+    #[account(mut)]
+    pub entrant_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGiveaway<'info> {
+    #[account(
+        mut,
+        close = host,
+        has_one = host @ ErrorCode::Unauthorized,
+        seeds = [b"giveaway", giveaway.host.as_ref(), &giveaway.giveaway_id.to_le_bytes()],
+        bump = giveaway.bump,
+    )]
+    pub giveaway: Box<Account<'info, Giveaway>>,
+
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// Only for SPL giveaways
+    #[account(
+        mut,
+        seeds = [b"giveaway-vault", giveaway.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL giveaways
+    #[account(mut)]
+    pub host_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct Giveaway {
+    pub host: Pubkey,                 // 32
+    pub giveaway_id: u64,             // 8
+    pub commitment: [u8; 32],         // 32
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub prize_per_winner: u64,        // 8
+    pub winners_count: u8,            // 1
+    pub draw_slot: u64,               // 8
+    pub claim_deadline: i64,          // 8
+    pub revealed: bool,               // 1
+    #[max_len(MAX_GIVEAWAY_ENTRANTS)]
+    pub entrants: Vec<Pubkey>,        // 4 + 200 * 32
+    #[max_len(MAX_GIVEAWAY_WINNERS)]
+    pub winners: Vec<GiveawayWinner>, // 4 + 25 * 33
+    pub bump: u8,                     // 1
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct GiveawayWinner {
+    pub winner: Pubkey,
+    pub claimed: bool,
+}
+
+// Events
+
+#[event]
+pub struct GiveawayCreated {
+    pub giveaway: Pubkey,
+    pub host: Pubkey,
+    pub commitment: [u8; 32],
+    pub mint: Option<Pubkey>,
+    pub prize_per_winner: u64,
+    pub winners_count: u8,
+    pub draw_slot: u64,
+}
+
+#[event]
+pub struct GiveawayEntered {
+    pub giveaway: Pubkey,
+    pub entrant: Pubkey,
+    pub entrants: u16,
+}
+
+#[event]
+pub struct GiveawayRevealed {
+    pub giveaway: Pubkey,
+    pub secret: [u8; 32],
+    pub slot_hash: [u8; 32],
+    pub winners: Vec<Pubkey>,
+}
+
+#[event]
+pub struct GiveawayPrizeClaimed {
+    pub giveaway: Pubkey,
+    pub winner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct GiveawayForfeitClaimed {
+    pub giveaway: Pubkey,
+    pub entrant: Pubkey,
+    pub amount: u64,
+}
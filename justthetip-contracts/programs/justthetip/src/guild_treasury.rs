@@ -0,0 +1,515 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, ErrorCode};
+
+/// Maximum length of a Discord guild id
+pub const MAX_GUILD_ID_LEN: usize = 32;
+/// Maximum number of admins on a guild treasury
+pub const MAX_GUILD_ADMINS: usize = 5;
+/// Maximum number of spenders on a guild treasury
+pub const MAX_GUILD_SPENDERS: usize = 20;
+
+/// Create the treasury for a guild. The creator becomes its first admin.
+/// Spender allowances reset every `period` seconds.
+pub(crate) fn create_guild_treasury(
+    ctx: Context<CreateGuildTreasury>,
+    guild_id: String,
+    period: i64,
+) -> Result<()> {
+    require!(
+        !guild_id.is_empty() && guild_id.len() <= MAX_GUILD_ID_LEN,
+        ErrorCode::InvalidGuildId
+    );
+    require!(period > 0, ErrorCode::InvalidInterval);
+
+    match (&ctx.accounts.mint, &ctx.accounts.vault) {
+        (Some(_), Some(_)) | (None, None) => {}
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    let treasury = &mut ctx.accounts.treasury;
+    treasury.creator = ctx.accounts.creator.key();
+    treasury.guild_id = guild_id;
+    treasury.mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+    treasury.admins = vec![ctx.accounts.creator.key()];
+    treasury.spenders = Vec::new();
+    treasury.period = period;
+    treasury.bump = ctx.bumps.treasury;
+
+    msg!("Guild treasury created for guild {}", treasury.guild_id);
+    Ok(())
+}
+
+/// Anyone can deposit into a guild treasury
+pub(crate) fn fund_guild_treasury(ctx: Context<FundGuildTreasury>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    match ctx.accounts.treasury.mint {
+        Some(mint) => {
+            let (Some(vault), Some(funder_token_account), Some(token_program)) = (
+                &ctx.accounts.vault,
+                &ctx.accounts.funder_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(funder_token_account.mint, mint, ErrorCode::MintMismatch);
+
+            let cpi_accounts = Transfer {
+                from: funder_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.funder.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)?;
+        }
+        None => {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+        }
+    }
+
+    msg!("Guild treasury funded with {}", amount);
+    Ok(())
+}
+
+/// Admin adds another admin
+pub(crate) fn add_guild_admin(ctx: Context<ManageGuildTreasury>, admin: Pubkey) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    require!(!treasury.is_admin(&admin), ErrorCode::AlreadyGuildMember);
+    require!(
+        treasury.admins.len() < MAX_GUILD_ADMINS,
+        ErrorCode::TooManyGuildMembers
+    );
+    treasury.admins.push(admin);
+
+    emit!(GuildRoleChanged {
+        treasury: treasury.key(),
+        changed_by: ctx.accounts.admin.key(),
+        member: admin,
+        role: GuildRole::Admin,
+        allowance: None,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Admin removes an admin. The last admin cannot be removed.
+pub(crate) fn remove_guild_admin(ctx: Context<ManageGuildTreasury>, admin: Pubkey) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    let index = treasury
+        .admins
+        .iter()
+        .position(|a| a == &admin)
+        .ok_or(ErrorCode::NotAGuildMember)?;
+    require!(treasury.admins.len() > 1, ErrorCode::LastGuildAdmin);
+    treasury.admins.remove(index);
+
+    emit!(GuildRoleChanged {
+        treasury: treasury.key(),
+        changed_by: ctx.accounts.admin.key(),
+        member: admin,
+        role: GuildRole::None,
+        allowance: None,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Admin adds a spender, or changes an existing spender's allowance. Usage in
+/// the current period is kept.
+pub(crate) fn set_guild_spender(
+    ctx: Context<ManageGuildTreasury>,
+    spender: Pubkey,
+    allowance: u64,
+) -> Result<()> {
+    require!(allowance > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let treasury = &mut ctx.accounts.treasury;
+    match treasury.spenders.iter_mut().find(|s| s.key == spender) {
+        Some(existing) => existing.allowance = allowance,
+        None => {
+            require!(
+                treasury.spenders.len() < MAX_GUILD_SPENDERS,
+                ErrorCode::TooManyGuildMembers
+            );
+            treasury.spenders.push(GuildSpender {
+                key: spender,
+                allowance,
+                spent: 0,
+                period_start: now,
+            });
+        }
+    }
+
+    emit!(GuildRoleChanged {
+        treasury: treasury.key(),
+        changed_by: ctx.accounts.admin.key(),
+        member: spender,
+        role: GuildRole::Spender,
+        allowance: Some(allowance),
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Admin removes a spender
+pub(crate) fn remove_guild_spender(ctx: Context<ManageGuildTreasury>, spender: Pubkey) -> Result<()> {
+    let treasury = &mut ctx.accounts.treasury;
+    let index = treasury
+        .spenders
+        .iter()
+        .position(|s| s.key == spender)
+        .ok_or(ErrorCode::NotAGuildMember)?;
+    treasury.spenders.remove(index);
+
+    emit!(GuildRoleChanged {
+        treasury: treasury.key(),
+        changed_by: ctx.accounts.admin.key(),
+        member: spender,
+        role: GuildRole::None,
+        allowance: None,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Admin withdraws from the treasury, SOL to their wallet or tokens to the
+/// given token account
+pub(crate) fn withdraw_guild_treasury(ctx: Context<WithdrawGuildTreasury>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let treasury = &ctx.accounts.treasury;
+    match treasury.mint {
+        Some(mint) => {
+            let (Some(vault), Some(destination_token_account), Some(token_program)) = (
+                &ctx.accounts.vault,
+                &ctx.accounts.destination_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(destination_token_account.mint, mint, ErrorCode::MintMismatch);
+
+            let seeds = &[b"guild-treasury", treasury.guild_id.as_bytes(), &[treasury.bump]];
+            let signer = &[&seeds[..]];
+            escrow::withdraw_tokens(
+                vault,
+                destination_token_account,
+                treasury.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )?;
+        }
+        None => {
+            escrow::withdraw_lamports(
+                &treasury.to_account_info(),
+                &ctx.accounts.admin.to_account_info(),
+                amount,
+            )?;
+        }
+    }
+
+    emit!(GuildWithdrawEvent {
+        treasury: treasury.key(),
+        admin: ctx.accounts.admin.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Charge a spender's allowance for a tip paid from the treasury. Usage
+/// resets once the spender's current period is over.
+pub(crate) fn charge_spender(
+    treasury: &mut Account<GuildTreasury>,
+    spender: Pubkey,
+    recipient: Pubkey,
+    mint: Option<Pubkey>,
+    amount: u64,
+    fee: u64,
+    now: i64,
+) -> Result<()> {
+    require!(treasury.mint == mint, ErrorCode::MintMismatch);
+
+    let period = treasury.period;
+    let entry = treasury
+        .spenders
+        .iter_mut()
+        .find(|s| s.key == spender)
+        .ok_or(ErrorCode::Unauthorized)?;
+    if now >= entry.period_start.saturating_add(period) {
+        entry.spent = 0;
+        entry.period_start = now;
+    }
+    let spent = entry.spent
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    require!(spent <= entry.allowance, ErrorCode::AllowanceExceeded);
+    entry.spent = spent;
+    let allowance = entry.allowance;
+
+    emit!(GuildSpendEvent {
+        treasury: treasury.key(),
+        spender,
+        recipient,
+        amount,
+        fee,
+        spent_this_period: spent,
+        allowance,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Pay part of a SOL tip, out of the guild treasury when the tip is drawn
+/// from one and from the sender's wallet otherwise
+pub(crate) fn pay_sol<'info>(
+    guild_treasury: Option<&Account<'info, GuildTreasury>>,
+    sender: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    destination: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    match guild_treasury {
+        Some(treasury) => escrow::withdraw_lamports(&treasury.to_account_info(), destination, amount),
+        None => {
+            let cpi_context = CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: sender.to_account_info(),
+                    to: destination.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)
+        }
+    }
+}
+
+/// Pay part of an SPL tip from `source`, which must be the guild vault when
+/// the tip is drawn from a guild treasury
+pub(crate) fn pay_spl<'info>(
+    guild_treasury: Option<&Account<'info, GuildTreasury>>,
+    sender: &Signer<'info>,
+    source: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    match guild_treasury {
+        Some(treasury) => {
+            require_keys_eq!(source.owner, treasury.key(), ErrorCode::AccountMismatch);
+
+            let seeds = &[b"guild-treasury", treasury.guild_id.as_bytes(), &[treasury.bump]];
+            let signer = &[&seeds[..]];
+            escrow::withdraw_tokens(
+                source,
+                destination,
+                treasury.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )
+        }
+        None => {
+            let cpi_accounts = Transfer {
+                from: source.to_account_info(),
+                to: destination.to_account_info(),
+                authority: sender.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)
+        }
+    }
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(guild_id: String)]
+pub struct CreateGuildTreasury<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + GuildTreasury::INIT_SPACE,
+        seeds = [b"guild-treasury", guild_id.as_bytes()],
+        bump
+    )]
+    pub treasury: Account<'info, GuildTreasury>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// Only for SPL treasuries
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL treasuries
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"guild-vault", treasury.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundGuildTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild-treasury", treasury.guild_id.as_bytes()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, GuildTreasury>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    /// Only for SPL treasuries
+    #[account(
+        mut,
+        seeds = [b"guild-vault", treasury.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL treasuries
+    #[account(mut)]
+    pub funder_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageGuildTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild-treasury", treasury.guild_id.as_bytes()],
+        bump = treasury.bump,
+        constraint = treasury.is_admin(&admin.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, GuildTreasury>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawGuildTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild-treasury", treasury.guild_id.as_bytes()],
+        bump = treasury.bump,
+        constraint = treasury.is_admin(&admin.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, GuildTreasury>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Only for SPL treasuries
+    #[account(
+        mut,
+        seeds = [b"guild-vault", treasury.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL treasuries
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct GuildTreasury {
+    pub creator: Pubkey,              // 32
+    #[max_len(32)]
+    pub guild_id: String,             // 4 + 32
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    #[max_len(5)]
+    pub admins: Vec<Pubkey>,          // 4 + 5 * 32
+    #[max_len(20)]
+    pub spenders: Vec<GuildSpender>,  // 4 + 20 * 56
+    pub period: i64,                  // 8
+    pub bump: u8,                     // 1
+}
+
+impl GuildTreasury {
+    pub fn is_admin(&self, key: &Pubkey) -> bool {
+        self.admins.contains(key)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct GuildSpender {
+    pub key: Pubkey,                  // 32
+    pub allowance: u64,               // 8 (per period)
+    pub spent: u64,                   // 8 (in the current period)
+    pub period_start: i64,            // 8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum GuildRole {
+    None,
+    Admin,
+    Spender,
+}
+
+// Events
+
+#[event]
+pub struct GuildRoleChanged {
+    pub treasury: Pubkey,
+    pub changed_by: Pubkey,
+    pub member: Pubkey,
+    pub role: GuildRole,              // None when removed
+    pub allowance: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuildSpendEvent {
+    pub treasury: Pubkey,
+    pub spender: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub spent_this_period: u64,
+    pub allowance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuildWithdrawEvent {
+    pub treasury: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -1,7 +1,42 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
+pub mod attester;
+pub mod bounty;
+pub mod config;
+pub mod escrow;
+pub mod fees;
+pub mod giveaway;
+pub mod guild_treasury;
+pub mod matching;
+pub mod migration;
+pub mod multisig;
+pub mod pending_tip;
+pub mod prize_pool;
+pub mod recovery;
+pub mod reputation;
+pub mod stream;
+pub mod subscription;
 pub mod trust_badge;
+pub mod voucher;
+
+pub use attester::*;
+pub use bounty::*;
+pub use config::*;
+pub use fees::*;
+pub use giveaway::*;
+pub use guild_treasury::*;
+pub use matching::*;
+pub use migration::*;
+pub use multisig::*;
+pub use pending_tip::*;
+pub use prize_pool::*;
+pub use recovery::*;
+pub use reputation::*;
+pub use stream::*;
+pub use subscription::*;
+pub use trust_badge::*;
+pub use voucher::*;
 
 // IMPORTANT: Update this program ID after deployment
 // This is a placeholder - run `anchor keys list` after building to get your actual program ID
@@ -22,23 +57,75 @@ pub mod justthetip {
         user_account.tip_count = 0;
         user_account.bump = ctx.bumps.user_account;
         
+        // Record who referred this user, if anyone
+        if let Some(referrer_account) = &ctx.accounts.referrer_account {
+            require!(
+                referrer_account.authority != user_account.authority,
+                ErrorCode::SelfReferral
+            );
+            user_account.referrer = Some(referrer_account.key());
+            user_account.referred_at = Clock::get()?.unix_timestamp;
+        }
+        
         msg!("User account initialized for {}", user_account.discord_id);
         Ok(())
     }
 
-    /// Send a SOL tip from one user to another
+    /// Grow a user account from before the referral and rate limit fields
+    pub fn migrate_user_account(ctx: Context<MigrateUserAccount>, discord_id: String) -> Result<()> {
+        migration::migrate_user_account(ctx, discord_id)
+    }
+
+    /// Send a SOL tip from one user to another, or from a guild treasury the
+    /// sender can spend from
     pub fn tip_sol(ctx: Context<TipSol>, amount: u64) -> Result<()> {
         require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        ctx.accounts.config.check_tip(
+            &mut ctx.accounts.sender_account,
+            None,
+            amount,
+            Clock::get()?.slot,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let fee = ctx.accounts.config.fee_for(amount);
+        let net_amount = amount - fee;
+        
+        // Large tips need a trusted sender
+        trust_badge::require_trust_score(
+            ctx.accounts.sender_badge.as_deref_mut(),
+            ctx.accounts.sender_badge_token_account.as_deref(),
+            &ctx.accounts.config,
+            ctx.accounts.config.tip_min_score(None, amount),
+            now,
+        )?;
+        
+        // Guild tips are charged to the spender's allowance
+        if let Some(treasury) = ctx.accounts.guild_treasury.as_deref_mut() {
+            guild_treasury::charge_spender(
+                treasury,
+                ctx.accounts.sender.key(),
+                ctx.accounts.recipient.key(),
+                None,
+                amount,
+                fee,
+                now,
+            )?;
+        }
         
         // Transfer SOL
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.sender.to_account_info(),
-                to: ctx.accounts.recipient.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(cpi_context, amount)?;
+        guild_treasury::pay_sol(
+            ctx.accounts.guild_treasury.as_deref(),
+            &ctx.accounts.sender,
+            &ctx.accounts.system_program,
+            &ctx.accounts.recipient,
+            net_amount,
+        )?;
+        
+        // Pay the protocol fee, sharing it with the sender's referrer
+        if fee > 0 {
+            fees::collect_sol_fee(ctx.accounts, fee, now)?;
+        }
         
         // Update sender stats
         let sender_account = &mut ctx.accounts.sender_account;
@@ -52,15 +139,43 @@ pub mod justthetip {
         // Update recipient stats
         let recipient_account = &mut ctx.accounts.recipient_account;
         recipient_account.total_received = recipient_account.total_received
-            .checked_add(amount)
+            .checked_add(net_amount)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
         
+        // Credit trust badge reputation for the tip
+        reputation::accrue_tip_reputation(
+            &ctx.accounts.config,
+            ctx.accounts.tip_pair.as_deref_mut(),
+            ctx.accounts.sender.key(),
+            ctx.accounts.recipient.key(),
+            [
+                ctx.accounts.sender_badge.as_deref_mut(),
+                ctx.accounts.recipient_badge.as_deref_mut(),
+            ],
+            now,
+        )?;
+        
+        // Top up from a sponsor's matching pool when one applies
+        if let Some(matching_pool) = &mut ctx.accounts.matching_pool {
+            let matched = matching::match_sol_tip(
+                matching_pool,
+                ctx.accounts.match_record.as_deref_mut(),
+                ctx.accounts.sender.key(),
+                &ctx.accounts.recipient,
+                amount,
+            )?;
+            recipient_account.total_received = recipient_account.total_received
+                .checked_add(matched)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+        
         emit!(TipEvent {
             sender: ctx.accounts.sender.key(),
             recipient: ctx.accounts.recipient.key(),
             amount,
             token_type: TokenType::Sol,
-            timestamp: Clock::get()?.unix_timestamp,
+            status: TipStatus::Sent,
+            timestamp: now,
         });
         
         msg!("Tip sent: {} lamports from {} to {}", 
@@ -72,11 +187,140 @@ pub mod justthetip {
         Ok(())
     }
 
-    /// Send an SPL token tip from one user to another
+    /// Send an SPL token tip from one user to another, or from a guild
+    /// treasury the sender can spend from
     pub fn tip_spl_token(ctx: Context<TipSplToken>, amount: u64) -> Result<()> {
         require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        ctx.accounts.config.check_tip(
+            &mut ctx.accounts.sender_account,
+            Some(ctx.accounts.sender_token_account.mint),
+            amount,
+            Clock::get()?.slot,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let fee = ctx.accounts.config.fee_for(amount);
+        let net_amount = amount - fee;
+        
+        // Large tips need a trusted sender
+        trust_badge::require_trust_score(
+            ctx.accounts.sender_badge.as_deref_mut(),
+            ctx.accounts.sender_badge_token_account.as_deref(),
+            &ctx.accounts.config,
+            ctx.accounts.config.tip_min_score(Some(ctx.accounts.sender_token_account.mint), amount),
+            now,
+        )?;
+        
+        // Guild tips are charged to the spender's allowance
+        if let Some(treasury) = ctx.accounts.guild_treasury.as_deref_mut() {
+            guild_treasury::charge_spender(
+                treasury,
+                ctx.accounts.sender.key(),
+                ctx.accounts.recipient_token_account.owner,
+                Some(ctx.accounts.sender_token_account.mint),
+                amount,
+                fee,
+                now,
+            )?;
+        }
         
         // Transfer SPL tokens
+        guild_treasury::pay_spl(
+            ctx.accounts.guild_treasury.as_deref(),
+            &ctx.accounts.sender,
+            &ctx.accounts.sender_token_account,
+            &ctx.accounts.recipient_token_account,
+            &ctx.accounts.token_program,
+            net_amount,
+        )?;
+        
+        // Pay the protocol fee, sharing it with the sender's referrer
+        if fee > 0 {
+            fees::collect_spl_fee(ctx.accounts, fee, now)?;
+        }
+        
+        // Update sender stats (in token units)
+        let sender_account = &mut ctx.accounts.sender_account;
+        sender_account.total_sent = sender_account.total_sent
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        sender_account.tip_count = sender_account.tip_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        
+        // Update recipient stats
+        let recipient_account = &mut ctx.accounts.recipient_account;
+        recipient_account.total_received = recipient_account.total_received
+            .checked_add(net_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        
+        // Credit trust badge reputation for the tip
+        reputation::accrue_tip_reputation(
+            &ctx.accounts.config,
+            ctx.accounts.tip_pair.as_deref_mut(),
+            ctx.accounts.sender.key(),
+            ctx.accounts.recipient_token_account.owner,
+            [
+                ctx.accounts.sender_badge.as_deref_mut(),
+                ctx.accounts.recipient_badge.as_deref_mut(),
+            ],
+            now,
+        )?;
+        
+        // Top up from a sponsor's matching pool when one applies
+        if let Some(matching_pool) = &mut ctx.accounts.matching_pool {
+            let matched = matching::match_spl_tip(
+                matching_pool,
+                ctx.accounts.match_record.as_deref_mut(),
+                ctx.accounts.matching_vault.as_ref(),
+                ctx.accounts.sender.key(),
+                &ctx.accounts.recipient_token_account,
+                &ctx.accounts.token_program,
+                amount,
+            )?;
+            recipient_account.total_received = recipient_account.total_received
+                .checked_add(matched)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+        }
+        
+        emit!(TipEvent {
+            sender: ctx.accounts.sender.key(),
+            recipient: ctx.accounts.recipient_token_account.owner,
+            amount,
+            token_type: TokenType::SplToken,
+            status: TipStatus::Sent,
+            timestamp: now,
+        });
+        
+        msg!("SPL token tip sent: {} tokens from {} to {}", 
+            amount, 
+            ctx.accounts.sender.key(), 
+            ctx.accounts.recipient_token_account.owner
+        );
+        
+        Ok(())
+    }
+
+    /// Send a single NFT from one user to another
+    pub fn tip_nft(ctx: Context<TipNft>) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        ctx.accounts.config.check_tip(
+            &mut ctx.accounts.sender_account,
+            Some(ctx.accounts.mint.key()),
+            1,
+            Clock::get()?.slot,
+        )?;
+        
+        // NFTs count as large tips
+        trust_badge::require_trust_score(
+            ctx.accounts.sender_badge.as_deref_mut(),
+            ctx.accounts.sender_badge_token_account.as_deref(),
+            &ctx.accounts.config,
+            ctx.accounts.config.nft_tip_min_score(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        // Transfer the NFT
         let cpi_accounts = Transfer {
             from: ctx.accounts.sender_token_account.to_account_info(),
             to: ctx.accounts.recipient_token_account.to_account_info(),
@@ -84,12 +328,12 @@ pub mod justthetip {
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
+        token::transfer(cpi_ctx, 1)?;
         
-        // Update sender stats (in token units)
+        // Update sender stats (one token)
         let sender_account = &mut ctx.accounts.sender_account;
         sender_account.total_sent = sender_account.total_sent
-            .checked_add(amount)
+            .checked_add(1)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
         sender_account.tip_count = sender_account.tip_count
             .checked_add(1)
@@ -98,37 +342,42 @@ pub mod justthetip {
         // Update recipient stats
         let recipient_account = &mut ctx.accounts.recipient_account;
         recipient_account.total_received = recipient_account.total_received
-            .checked_add(amount)
+            .checked_add(1)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
         
         emit!(TipEvent {
             sender: ctx.accounts.sender.key(),
-            recipient: ctx.accounts.recipient.key(),
-            amount,
-            token_type: TokenType::SplToken,
+            recipient: recipient_account.authority,
+            amount: 1,
+            token_type: TokenType::Nft,
+            status: TipStatus::Sent,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
-        msg!("SPL token tip sent: {} tokens from {} to {}", 
-            amount, 
+        msg!("NFT tip sent: {} from {} to {}", 
+            ctx.accounts.mint.key(), 
             ctx.accounts.sender.key(), 
-            ctx.accounts.recipient.key()
+            recipient_account.authority
         );
         
         Ok(())
     }
 
-    /// Create a multi-recipient airdrop
+    /// Create a multi-recipient airdrop, escrowing its full amount
     pub fn create_airdrop(
         ctx: Context<CreateAirdrop>,
+        airdrop_id: u64,
         total_amount: u64,
         recipients_count: u8,
+        voucher_signer: Option<Pubkey>,
+        min_claim_score: u64,
     ) -> Result<()> {
         require!(total_amount > 0, ErrorCode::InvalidAmount);
         require!(recipients_count > 0 && recipients_count <= 50, ErrorCode::TooManyRecipients);
         
         let airdrop = &mut ctx.accounts.airdrop;
         airdrop.creator = ctx.accounts.creator.key();
+        airdrop.airdrop_id = airdrop_id;
         airdrop.total_amount = total_amount;
         airdrop.amount_per_recipient = total_amount / recipients_count as u64;
         airdrop.recipients_count = recipients_count;
@@ -136,6 +385,30 @@ pub mod justthetip {
         airdrop.is_active = true;
         airdrop.created_at = Clock::get()?.unix_timestamp;
         airdrop.bump = ctx.bumps.airdrop;
+        airdrop.voucher_signer = voucher_signer;
+        airdrop.claimed_amount = 0;
+        airdrop.min_claim_score = min_claim_score;
+        
+        // Large airdrops need an approved multisig proposal
+        let large_airdrop_threshold = ctx.accounts.config.large_airdrop_threshold;
+        if large_airdrop_threshold > 0 && total_amount >= large_airdrop_threshold {
+            multisig::consume_airdrop_approval(
+                ctx.accounts.approval.as_mut(),
+                airdrop.creator,
+                total_amount,
+                recipients_count,
+            )?;
+        }
+        
+        // Escrow the whole airdrop in its PDA
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.creator.to_account_info(),
+                to: ctx.accounts.airdrop.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, total_amount)?;
         
         msg!("Airdrop created: {} lamports for {} recipients", total_amount, recipients_count);
         
@@ -144,31 +417,25 @@ pub mod justthetip {
 
     /// Claim from an airdrop
     pub fn claim_airdrop(ctx: Context<ClaimAirdrop>) -> Result<()> {
+        // Keep fresh wallets from farming airdrops
+        trust_badge::require_trust_score(
+            ctx.accounts.claimer_badge.as_deref_mut(),
+            ctx.accounts.claimer_badge_token_account.as_deref(),
+            &ctx.accounts.config,
+            ctx.accounts.airdrop.min_claim_score.max(ctx.accounts.config.airdrop_min_score),
+            Clock::get()?.unix_timestamp,
+        )?;
+        
         let airdrop = &mut ctx.accounts.airdrop;
         
         require!(airdrop.is_active, ErrorCode::AirdropInactive);
+        require!(airdrop.voucher_signer.is_none(), ErrorCode::VoucherRequired);
         require!(airdrop.claimed_count < airdrop.recipients_count, ErrorCode::AirdropFullyClaimed);
         
         let amount = airdrop.amount_per_recipient;
         
-        // Transfer SOL from airdrop PDA to claimer
-        let seeds = &[
-            b"airdrop",
-            airdrop.creator.as_ref(),
-            &airdrop.created_at.to_le_bytes(),
-            &[airdrop.bump],
-        ];
-        let signer = &[&seeds[..]];
-        
-        let cpi_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.airdrop.to_account_info(),
-                to: ctx.accounts.claimer.to_account_info(),
-            },
-            signer,
-        );
-        anchor_lang::system_program::transfer(cpi_context, amount)?;
+        // Pay the claimer out of the airdrop escrow
+        escrow::withdraw_lamports(&airdrop.to_account_info(), &ctx.accounts.claimer, amount)?;
         
         airdrop.claimed_count = airdrop.claimed_count
             .checked_add(1)
@@ -179,6 +446,14 @@ pub mod justthetip {
             airdrop.is_active = false;
         }
         
+        // The claim record keeps this wallet from claiming again
+        let claim = &mut ctx.accounts.claim;
+        claim.airdrop = ctx.accounts.airdrop.key();
+        claim.claimer = ctx.accounts.claimer.key();
+        claim.amount = amount;
+        claim.claimed_at = Clock::get()?.unix_timestamp;
+        claim.bump = ctx.bumps.claim;
+        
         emit!(AirdropClaimEvent {
             airdrop: ctx.accounts.airdrop.key(),
             claimer: ctx.accounts.claimer.key(),
@@ -191,6 +466,15 @@ pub mod justthetip {
         Ok(())
     }
 
+    /// Claim from a voucher airdrop with a signed voucher
+    pub fn claim_airdrop_voucher(
+        ctx: Context<ClaimAirdropVoucher>,
+        amount: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        voucher::claim_airdrop_voucher(ctx, amount, expiry)
+    }
+
     /// Close an airdrop and return remaining funds
     pub fn close_airdrop(ctx: Context<CloseAirdrop>) -> Result<()> {
         let airdrop = &ctx.accounts.airdrop;
@@ -204,6 +488,364 @@ pub mod justthetip {
         
         Ok(())
     }
+
+    /// Create a recurring tip paid out every `interval` seconds
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        amount: u64,
+        interval: i64,
+        cycles: u32,
+    ) -> Result<()> {
+        subscription::create_subscription(ctx, amount, interval, cycles)
+    }
+
+    /// Pay out a due subscription cycle (permissionless crank)
+    pub fn execute_subscription(ctx: Context<ExecuteSubscription>) -> Result<()> {
+        subscription::execute_subscription(ctx)
+    }
+
+    /// Cancel a subscription and refund anything left in escrow
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        subscription::cancel_subscription(ctx)
+    }
+
+    /// Start a tip stream that vests linearly between two timestamps
+    pub fn start_stream(
+        ctx: Context<StartStream>,
+        amount: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        stream::start_stream(ctx, amount, start_time, end_time)
+    }
+
+    /// Withdraw the vested part of a tip stream
+    pub fn withdraw_stream(ctx: Context<WithdrawStream>) -> Result<()> {
+        stream::withdraw_stream(ctx)
+    }
+
+    /// Cancel a tip stream and refund the unvested remainder
+    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+        stream::cancel_stream(ctx)
+    }
+
+    /// Send a tip held in escrow until the recipient claims it
+    pub fn send_escrowed_tip(
+        ctx: Context<SendEscrowedTip>,
+        tip_id: u64,
+        amount: u64,
+        cancel_window: i64,
+    ) -> Result<()> {
+        pending_tip::send_escrowed_tip(ctx, tip_id, amount, cancel_window)
+    }
+
+    /// Claim an escrowed tip
+    pub fn claim_tip(ctx: Context<ClaimTip>) -> Result<()> {
+        pending_tip::claim_tip(ctx)
+    }
+
+    /// Cancel an unclaimed escrowed tip within its cancellation window
+    pub fn cancel_tip(ctx: Context<CancelTip>) -> Result<()> {
+        pending_tip::cancel_tip(ctx)
+    }
+
+    /// Post a bounty escrow released by an approver
+    pub fn create_bounty(
+        ctx: Context<CreateBounty>,
+        bounty_id: u64,
+        amount: u64,
+        deadline: i64,
+        approver: Option<Pubkey>,
+    ) -> Result<()> {
+        bounty::create_bounty(ctx, bounty_id, amount, deadline, approver)
+    }
+
+    /// Pay a bounty to the chosen recipient
+    pub fn release_bounty(ctx: Context<ReleaseBounty>) -> Result<()> {
+        bounty::release_bounty(ctx)
+    }
+
+    /// Return an expired bounty to its creator
+    pub fn refund_bounty(ctx: Context<RefundBounty>) -> Result<()> {
+        bounty::refund_bounty(ctx)
+    }
+
+    /// Fund a prize pool for a trivia round
+    pub fn create_prize_pool(
+        ctx: Context<CreatePrizePool>,
+        pool_id: u64,
+        amount: u64,
+        settlement_authority: Pubkey,
+        claim_deadline: i64,
+    ) -> Result<()> {
+        prize_pool::create_prize_pool(ctx, pool_id, amount, settlement_authority, claim_deadline)
+    }
+
+    /// Record the winners of a prize pool
+    pub fn settle_prize_pool(ctx: Context<SettlePrizePool>, payouts: Vec<PrizePayout>) -> Result<()> {
+        prize_pool::settle_prize_pool(ctx, payouts)
+    }
+
+    /// Claim a prize from a settled pool
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        prize_pool::claim_prize(ctx)
+    }
+
+    /// Close a prize pool and refund what is left to the host
+    pub fn close_prize_pool(ctx: Context<ClosePrizePool>) -> Result<()> {
+        prize_pool::close_prize_pool(ctx)
+    }
+
+    /// Create a commit-reveal giveaway with an escrowed prize
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_giveaway(
+        ctx: Context<CreateGiveaway>,
+        giveaway_id: u64,
+        commitment: [u8; 32],
+        prize_per_winner: u64,
+        winners_count: u8,
+        draw_slot: u64,
+        claim_deadline: i64,
+    ) -> Result<()> {
+        giveaway::create_giveaway(
+            ctx,
+            giveaway_id,
+            commitment,
+            prize_per_winner,
+            winners_count,
+            draw_slot,
+            claim_deadline,
+        )
+    }
+
+    /// Enter a giveaway before its draw slot
+    pub fn enter_giveaway(ctx: Context<EnterGiveaway>) -> Result<()> {
+        giveaway::enter_giveaway(ctx)
+    }
+
+    /// Reveal the giveaway secret and draw the winners
+    pub fn reveal_giveaway(ctx: Context<RevealGiveaway>, secret: [u8; 32]) -> Result<()> {
+        giveaway::reveal_giveaway(ctx, secret)
+    }
+
+    /// Claim a giveaway prize
+    pub fn claim_giveaway_prize(ctx: Context<ClaimGiveawayPrize>) -> Result<()> {
+        giveaway::claim_giveaway_prize(ctx)
+    }
+
+    /// Claim an entrant's share of a giveaway the host never revealed
+    pub fn claim_forfeited_giveaway(ctx: Context<ClaimForfeitedGiveaway>) -> Result<()> {
+        giveaway::claim_forfeited_giveaway(ctx)
+    }
+
+    /// Close a giveaway and refund unclaimed prizes to the host
+    pub fn close_giveaway(ctx: Context<CloseGiveaway>) -> Result<()> {
+        giveaway::close_giveaway(ctx)
+    }
+
+    /// Create a sponsor pool that matches tips up to a cap
+    pub fn create_matching_pool(
+        ctx: Context<CreateMatchingPool>,
+        pool_id: u64,
+        beneficiary: Option<Pubkey>,
+        match_ratio_bps: u16,
+        cap: u64,
+        sender_cap: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        matching::create_matching_pool(
+            ctx,
+            pool_id,
+            beneficiary,
+            match_ratio_bps,
+            cap,
+            sender_cap,
+            expiry,
+        )
+    }
+
+    /// Open a sender's match counter so their tips can be matched by a pool
+    pub fn open_match_record(ctx: Context<OpenMatchRecord>) -> Result<()> {
+        matching::open_match_record(ctx)
+    }
+
+    /// Close a spent or expired matching pool and refund the sponsor
+    pub fn close_matching_pool(ctx: Context<CloseMatchingPool>) -> Result<()> {
+        matching::close_matching_pool(ctx)
+    }
+
+    /// Create the program config (upgrade authority only)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+        config::initialize_config(ctx, params)
+    }
+
+    /// Queue a timelocked config change
+    pub fn queue_config_change(ctx: Context<ManageConfig>, params: ConfigParams) -> Result<()> {
+        config::queue_config_change(ctx, params)
+    }
+
+    /// Apply the queued config change once its delay has passed
+    pub fn apply_config_change(ctx: Context<ApplyConfigChange>) -> Result<()> {
+        config::apply_config_change(ctx)
+    }
+
+    /// Cancel the queued config change
+    pub fn cancel_config_change(ctx: Context<ManageConfig>) -> Result<()> {
+        config::cancel_config_change(ctx)
+    }
+
+    /// Withdraw SOL referral earnings from a user account
+    pub fn withdraw_referral_earnings(ctx: Context<WithdrawReferralEarnings>) -> Result<()> {
+        fees::withdraw_referral_earnings(ctx)
+    }
+
+    /// Create a shared treasury for a Discord guild
+    pub fn create_guild_treasury(
+        ctx: Context<CreateGuildTreasury>,
+        guild_id: String,
+        period: i64,
+    ) -> Result<()> {
+        guild_treasury::create_guild_treasury(ctx, guild_id, period)
+    }
+
+    /// Deposit into a guild treasury
+    pub fn fund_guild_treasury(ctx: Context<FundGuildTreasury>, amount: u64) -> Result<()> {
+        guild_treasury::fund_guild_treasury(ctx, amount)
+    }
+
+    /// Add a guild treasury admin
+    pub fn add_guild_admin(ctx: Context<ManageGuildTreasury>, admin: Pubkey) -> Result<()> {
+        guild_treasury::add_guild_admin(ctx, admin)
+    }
+
+    /// Remove a guild treasury admin
+    pub fn remove_guild_admin(ctx: Context<ManageGuildTreasury>, admin: Pubkey) -> Result<()> {
+        guild_treasury::remove_guild_admin(ctx, admin)
+    }
+
+    /// Add a guild treasury spender or change their allowance
+    pub fn set_guild_spender(
+        ctx: Context<ManageGuildTreasury>,
+        spender: Pubkey,
+        allowance: u64,
+    ) -> Result<()> {
+        guild_treasury::set_guild_spender(ctx, spender, allowance)
+    }
+
+    /// Remove a guild treasury spender
+    pub fn remove_guild_spender(ctx: Context<ManageGuildTreasury>, spender: Pubkey) -> Result<()> {
+        guild_treasury::remove_guild_spender(ctx, spender)
+    }
+
+    /// Withdraw from a guild treasury as one of its admins
+    pub fn withdraw_guild_treasury(ctx: Context<WithdrawGuildTreasury>, amount: u64) -> Result<()> {
+        guild_treasury::withdraw_guild_treasury(ctx, amount)
+    }
+
+    /// Set up the M-of-N signer set that administers the config
+    pub fn initialize_multisig(
+        ctx: Context<InitializeMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        proposal_lifetime: i64,
+    ) -> Result<()> {
+        multisig::initialize_multisig(ctx, signers, threshold, proposal_lifetime)
+    }
+
+    /// Propose a treasury withdrawal, large airdrop or config change
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        multisig::create_proposal(ctx, action)
+    }
+
+    /// Approve a pending proposal
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        multisig::approve_proposal(ctx)
+    }
+
+    /// Execute a proposal that has reached the threshold
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        multisig::execute_proposal(ctx)
+    }
+
+    /// Close a finished or expired proposal
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        multisig::close_proposal(ctx)
+    }
+
+    /// Register guardians who can recover a user account
+    pub fn set_guardians(
+        ctx: Context<SetGuardians>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        delay: i64,
+    ) -> Result<()> {
+        recovery::set_guardians(ctx, guardians, threshold, delay)
+    }
+
+    /// Replace a user account's guardians
+    pub fn update_guardians(
+        ctx: Context<UpdateGuardians>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        delay: i64,
+    ) -> Result<()> {
+        recovery::update_guardians(ctx, guardians, threshold, delay)
+    }
+
+    /// Guardian approves moving a user account to a new wallet
+    pub fn approve_recovery(ctx: Context<ApproveRecovery>, new_authority: Pubkey) -> Result<()> {
+        recovery::approve_recovery(ctx, new_authority)
+    }
+
+    /// Current authority vetoes a recovery in progress
+    pub fn veto_recovery(ctx: Context<VetoRecovery>) -> Result<()> {
+        recovery::veto_recovery(ctx)
+    }
+
+    /// Complete a recovery once its delay has passed
+    pub fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+        recovery::execute_recovery(ctx)
+    }
+
+    /// Admin creates the trust badge attester registry
+    pub fn initialize_attester_registry(ctx: Context<InitializeAttesterRegistry>) -> Result<()> {
+        attester::initialize_attester_registry(ctx)
+    }
+
+    /// Admin sets an attester's weight (0 removes it)
+    pub fn set_attester(ctx: Context<ManageAttesters>, attester: Pubkey, weight: u16) -> Result<()> {
+        attester::set_attester(ctx, attester, weight)
+    }
+
+    /// Mint a trust badge to a user
+    pub fn mint_badge(ctx: Context<MintBadge>, initial_score: u64) -> Result<()> {
+        trust_badge::mint_badge(ctx, initial_score)
+    }
+
+    /// Attester adjusts its contribution to a badge's reputation score
+    pub fn update_score(
+        ctx: Context<UpdateScore>,
+        delta: i64,
+        mode: ScoreMode,
+        reason: u16,
+    ) -> Result<()> {
+        trust_badge::update_score(ctx, delta, mode, reason)
+    }
+
+    /// An attester or the config admin revokes and burns a badge
+    pub fn revoke_badge(ctx: Context<RevokeBadge>, reason: u16) -> Result<()> {
+        trust_badge::revoke_badge(ctx, reason)
+    }
+
+    /// Open the tip counter for two wallets so their tips can earn reputation
+    pub fn open_tip_pair(ctx: Context<OpenTipPair>) -> Result<()> {
+        reputation::open_tip_pair(ctx)
+    }
+
+    /// Return a badge's decayed score and tier as return data
+    pub fn get_score(ctx: Context<GetScore>) -> Result<BadgeScore> {
+        trust_badge::get_score(ctx)
+    }
 }
 
 // Context Structs
@@ -223,6 +865,13 @@ pub struct InitializeUser<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
+    /// Optional user who referred this one
+    #[account(
+        seeds = [b"user", referrer_account.discord_id.as_bytes()],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Option<Account<'info, UserAccount>>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -232,6 +881,7 @@ pub struct TipSol<'info> {
         mut,
         seeds = [b"user", sender_account.discord_id.as_bytes()],
         bump = sender_account.bump,
+        constraint = sender_account.authority == sender.key() @ ErrorCode::AccountMismatch,
     )]
     pub sender_account: Account<'info, UserAccount>,
     
@@ -246,9 +896,59 @@ pub struct TipSol<'info> {
     pub sender: Signer<'info>,
     
     /// CHECK: This is safe because we're just transferring SOL
-    #[account(mut)]
+    #[account(mut, address = recipient_account.authority @ ErrorCode::AccountMismatch)]
     pub recipient: AccountInfo<'info>,
     
+    /// Optional sponsor pool that matches this tip
+    #[account(mut)]
+    pub matching_pool: Option<Account<'info, MatchingPool>>,
+    
+    /// Sender's match counter, required when the pool pays a match
+    #[account(mut)]
+    pub match_record: Option<Box<Account<'info, MatchRecord>>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Receives protocol fees; must match the config treasury
+    #[account(mut, address = config.treasury @ ErrorCode::AccountMismatch)]
+    pub treasury: AccountInfo<'info>,
+    
+    /// Sender's referrer, required while they are owed a fee share
+    #[account(mut)]
+    pub referrer_account: Option<Box<Account<'info, UserAccount>>>,
+    
+    /// Sender's trust badge, to earn reputation for the tip
+    #[account(
+        mut,
+        seeds = [b"trust-badge", sender.key().as_ref()],
+        bump = sender_badge.bump,
+    )]
+    pub sender_badge: Option<Box<Account<'info, TrustBadge>>>,
+    
+    /// Sender's badge token account, required with `sender_badge` for gated tips
+    pub sender_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Recipient's trust badge, to earn reputation for the tip
+    #[account(
+        mut,
+        seeds = [b"trust-badge", recipient.key().as_ref()],
+        bump = recipient_badge.bump,
+    )]
+    pub recipient_badge: Option<Box<Account<'info, TrustBadge>>>,
+    
+    /// Tip counter for the sender and recipient, for the badges to earn reputation
+    #[account(mut)]
+    pub tip_pair: Option<Box<Account<'info, TipPair>>>,
+    
+    /// Guild treasury that pays the tip, when the sender is one of its spenders
+    #[account(
+        mut,
+        seeds = [b"guild-treasury", guild_treasury.guild_id.as_bytes()],
+        bump = guild_treasury.bump,
+    )]
+    pub guild_treasury: Option<Box<Account<'info, GuildTreasury>>>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -258,6 +958,7 @@ pub struct TipSplToken<'info> {
         mut,
         seeds = [b"user", sender_account.discord_id.as_bytes()],
         bump = sender_account.bump,
+        constraint = sender_account.authority == sender.key() @ ErrorCode::AccountMismatch,
     )]
     pub sender_account: Account<'info, UserAccount>,
     
@@ -274,19 +975,137 @@ pub struct TipSplToken<'info> {
     #[account(mut)]
     pub sender_token_account: Account<'info, TokenAccount>,
     
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == recipient_account.authority @ ErrorCode::AccountMismatch,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    
+    /// Optional sponsor pool that matches this tip
     #[account(mut)]
+    pub matching_pool: Option<Account<'info, MatchingPool>>,
+    
+    /// Sender's match counter, required when the pool pays a match
+    #[account(mut)]
+    pub match_record: Option<Box<Account<'info, MatchRecord>>>,
+    
+    /// Token vault of the matching pool
+    #[account(mut)]
+    pub matching_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+    
+    /// Treasury's token account, required while a protocol fee is set
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Sender's referrer, required while they are owed a fee share
+    #[account(mut)]
+    pub referrer_account: Option<Box<Account<'info, UserAccount>>>,
+    
+    /// Referrer's token account for their fee share
+    #[account(mut)]
+    pub referrer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Sender's trust badge, to earn reputation for the tip
+    #[account(
+        mut,
+        seeds = [b"trust-badge", sender.key().as_ref()],
+        bump = sender_badge.bump,
+    )]
+    pub sender_badge: Option<Box<Account<'info, TrustBadge>>>,
+    
+    /// Sender's badge token account, required with `sender_badge` for gated tips
+    pub sender_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Recipient's trust badge, to earn reputation for the tip
+    #[account(
+        mut,
+        seeds = [b"trust-badge", recipient_token_account.owner.as_ref()],
+        bump = recipient_badge.bump,
+    )]
+    pub recipient_badge: Option<Box<Account<'info, TrustBadge>>>,
+    
+    /// Tip counter for the sender and recipient, for the badges to earn reputation
+    #[account(mut)]
+    pub tip_pair: Option<Box<Account<'info, TipPair>>>,
+    
+    /// Guild treasury that pays the tip, when the sender is one of its
+    /// spenders. `sender_token_account` is then the guild vault.
+    #[account(
+        mut,
+        seeds = [b"guild-treasury", guild_treasury.guild_id.as_bytes()],
+        bump = guild_treasury.bump,
+    )]
+    pub guild_treasury: Option<Box<Account<'info, GuildTreasury>>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TipNft<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", sender_account.discord_id.as_bytes()],
+        bump = sender_account.bump,
+        constraint = sender_account.authority == sender.key() @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+    
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Must be an NFT: supply of one and no decimals
+    #[account(constraint = mint.supply == 1 && mint.decimals == 0 @ ErrorCode::NotAnNft)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = sender_token_account.owner == sender.key() @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = recipient_token_account.owner == recipient_account.authority @ ErrorCode::AccountMismatch,
+    )]
     pub recipient_token_account: Account<'info, TokenAccount>,
     
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+    
+    /// Sender's trust badge, required while large tips are gated
+    #[account(
+        mut,
+        seeds = [b"trust-badge", sender.key().as_ref()],
+        bump = sender_badge.bump,
+    )]
+    pub sender_badge: Option<Box<Account<'info, TrustBadge>>>,
+    
+    /// Sender's badge token account, required with `sender_badge` for gated tips
+    pub sender_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
+#[instruction(airdrop_id: u64)]
 pub struct CreateAirdrop<'info> {
     #[account(
         init,
         payer = creator,
         space = 8 + Airdrop::INIT_SPACE,
-        seeds = [b"airdrop", creator.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
+        seeds = [b"airdrop", creator.key().as_ref(), &airdrop_id.to_le_bytes()],
         bump
     )]
     pub airdrop: Account<'info, Airdrop>,
@@ -294,6 +1113,13 @@ pub struct CreateAirdrop<'info> {
     #[account(mut)]
     pub creator: Signer<'info>,
     
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    /// Executed multisig proposal approving this airdrop, when it is large
+    #[account(mut)]
+    pub approval: Option<Account<'info, Proposal>>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -301,15 +1127,38 @@ pub struct CreateAirdrop<'info> {
 pub struct ClaimAirdrop<'info> {
     #[account(
         mut,
-        seeds = [b"airdrop", airdrop.creator.as_ref(), &airdrop.created_at.to_le_bytes()],
+        seeds = [b"airdrop", airdrop.creator.as_ref(), &airdrop.airdrop_id.to_le_bytes()],
         bump = airdrop.bump,
     )]
     pub airdrop: Account<'info, Airdrop>,
     
-    /// CHECK: This is safe because we're just transferring SOL
+    /// One claim per wallet per airdrop
+    #[account(
+        init,
+        payer = claimer,
+        space = 8 + AirdropClaim::INIT_SPACE,
+        seeds = [b"airdrop-claim", airdrop.key().as_ref(), claimer.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, AirdropClaim>,
+    
     #[account(mut)]
     pub claimer: Signer<'info>,
     
+    /// Claimer's trust badge, required when the airdrop has a minimum score
+    #[account(
+        mut,
+        seeds = [b"trust-badge", claimer.key().as_ref()],
+        bump = claimer_badge.bump,
+    )]
+    pub claimer_badge: Option<Box<Account<'info, TrustBadge>>>,
+    
+    /// Claimer's badge token account, required with `claimer_badge` when the airdrop has a minimum score
+    pub claimer_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -318,7 +1167,7 @@ pub struct CloseAirdrop<'info> {
     #[account(
         mut,
         close = creator,
-        seeds = [b"airdrop", airdrop.creator.as_ref(), &airdrop.created_at.to_le_bytes()],
+        seeds = [b"airdrop", airdrop.creator.as_ref(), &airdrop.airdrop_id.to_le_bytes()],
         bump = airdrop.bump,
     )]
     pub airdrop: Account<'info, Airdrop>,
@@ -341,12 +1190,18 @@ pub struct UserAccount {
     pub total_received: u64,          // 8
     pub tip_count: u64,               // 8
     pub bump: u8,                     // 1
+    pub referrer: Option<Pubkey>,     // 1 + 32 (referrer's user PDA)
+    pub referred_at: i64,             // 8
+    pub referral_earnings: u64,       // 8
+    pub rate_window_start: u64,       // 8 (slot)
+    pub rate_window_tips: u16,        // 2
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct Airdrop {
     pub creator: Pubkey,              // 32
+    pub airdrop_id: u64,              // 8
     pub total_amount: u64,            // 8
     pub amount_per_recipient: u64,    // 8
     pub recipients_count: u8,         // 1
@@ -354,6 +1209,9 @@ pub struct Airdrop {
     pub is_active: bool,              // 1
     pub created_at: i64,              // 8
     pub bump: u8,                     // 1
+    pub voucher_signer: Option<Pubkey>, // 1 + 32 (claims need its signed voucher)
+    pub claimed_amount: u64,          // 8
+    pub min_claim_score: u64,         // 8 (trust score needed to claim)
 }
 
 // Events
@@ -364,6 +1222,7 @@ pub struct TipEvent {
     pub recipient: Pubkey,
     pub amount: u64,
     pub token_type: TokenType,
+    pub status: TipStatus,
     pub timestamp: i64,
 }
 
@@ -381,6 +1240,14 @@ pub struct AirdropClaimEvent {
 pub enum TokenType {
     Sol,
     SplToken,
+    Nft,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum TipStatus {
+    Sent,
+    Claimed,
+    Cancelled,
 }
 
 // Error Codes
@@ -399,4 +1266,170 @@ pub enum ErrorCode {
     Unauthorized,
     #[msg("Arithmetic overflow occurred")]
     ArithmeticOverflow,
+    #[msg("Invalid interval: must be greater than 0")]
+    InvalidInterval,
+    #[msg("Subscription payment is not due yet")]
+    SubscriptionNotDue,
+    #[msg("Subscription has no remaining cycles")]
+    SubscriptionComplete,
+    #[msg("Token account mint does not match")]
+    MintMismatch,
+    #[msg("Token accounts are required for SPL token transfers")]
+    MissingTokenAccounts,
+    #[msg("Account does not belong to the expected wallet")]
+    AccountMismatch,
+    #[msg("Invalid stream window: must start now or later and end after the start")]
+    InvalidStreamWindow,
+    #[msg("Nothing has vested to withdraw")]
+    NothingToWithdraw,
+    #[msg("Invalid cancel window: must be between 1 second and 7 days")]
+    InvalidCancelWindow,
+    #[msg("The cancellation window for this tip has closed")]
+    CancelWindowClosed,
+    #[msg("Invalid deadline: must be in the future")]
+    InvalidDeadline,
+    #[msg("Bounty deadline has passed")]
+    BountyExpired,
+    #[msg("Bounty deadline has not passed yet")]
+    BountyNotExpired,
+    #[msg("Invalid number of winners: between 1 and 25 allowed")]
+    TooManyWinners,
+    #[msg("A winner appears more than once")]
+    DuplicateWinner,
+    #[msg("Payouts exceed the prize pool")]
+    PayoutsExceedPool,
+    #[msg("Prize pool has already been settled")]
+    PoolAlreadySettled,
+    #[msg("Prize pool has not been settled")]
+    PoolNotSettled,
+    #[msg("Claim deadline has passed")]
+    ClaimDeadlinePassed,
+    #[msg("Caller is not a winner")]
+    NotAWinner,
+    #[msg("Prize has already been claimed")]
+    PrizeAlreadyClaimed,
+    #[msg("Prizes can still be claimed")]
+    PoolStillClaimable,
+    #[msg("Giveaway entries are closed")]
+    EntriesClosed,
+    #[msg("Giveaway is full")]
+    GiveawayFull,
+    #[msg("Already entered this giveaway")]
+    AlreadyEntered,
+    #[msg("Giveaway has already been revealed")]
+    AlreadyRevealed,
+    #[msg("Giveaway has not been revealed")]
+    NotRevealed,
+    #[msg("The draw slot has not passed yet")]
+    DrawSlotNotReached,
+    #[msg("Secret does not match the commitment")]
+    CommitmentMismatch,
+    #[msg("The draw slot hash is no longer available")]
+    SlotHashUnavailable,
+    #[msg("Invalid match ratio: must be greater than 0")]
+    InvalidMatchRatio,
+    #[msg("Matching pool is still active")]
+    MatchingPoolActive,
+    #[msg("Fee settings are out of range")]
+    InvalidFee,
+    #[msg("The sender's referrer account is required")]
+    MissingReferrer,
+    #[msg("Users cannot refer themselves")]
+    SelfReferral,
+    #[msg("Invalid guild id: must be 1 to 32 characters")]
+    InvalidGuildId,
+    #[msg("Key already has this guild role")]
+    AlreadyGuildMember,
+    #[msg("Key does not have this guild role")]
+    NotAGuildMember,
+    #[msg("Guild role list is full")]
+    TooManyGuildMembers,
+    #[msg("Cannot remove the last guild admin")]
+    LastGuildAdmin,
+    #[msg("Spend exceeds the spender's allowance for this period")]
+    AllowanceExceeded,
+    #[msg("Invalid signer set: 1 to 10 unique signers allowed")]
+    InvalidSignerSet,
+    #[msg("Invalid threshold: must be between 1 and the number of signers")]
+    InvalidThreshold,
+    #[msg("Caller is not a multisig signer")]
+    NotASigner,
+    #[msg("Signer has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("Proposal is no longer pending")]
+    ProposalNotPending,
+    #[msg("Proposal does not have enough approvals")]
+    ThresholdNotMet,
+    #[msg("Proposal is still pending")]
+    ProposalActive,
+    #[msg("This airdrop needs an executed multisig approval")]
+    ApprovalRequired,
+    #[msg("A config change is already queued")]
+    ConfigChangePending,
+    #[msg("No config change is queued")]
+    NoPendingConfigChange,
+    #[msg("The queued config change is still timelocked")]
+    ConfigChangeNotReady,
+    #[msg("Tipping is paused")]
+    ProgramPaused,
+    #[msg("Recovery delay must be at least 1 day")]
+    InvalidRecoveryDelay,
+    #[msg("Caller is not a guardian")]
+    NotAGuardian,
+    #[msg("Recovery must move the account to a new wallet")]
+    InvalidRecoveryTarget,
+    #[msg("A recovery to a different wallet is already in progress")]
+    RecoveryMismatch,
+    #[msg("No recovery is in progress")]
+    NoPendingRecovery,
+    #[msg("Recovery has not reached its threshold or delay")]
+    RecoveryNotReady,
+    #[msg("Tip minimums or rate limit settings are invalid")]
+    InvalidTipLimits,
+    #[msg("Tip is below the minimum for this token")]
+    TipBelowMinimum,
+    #[msg("Too many tips from this sender; try again later")]
+    TipRateLimited,
+    #[msg("Mint is not an NFT: supply must be 1 with 0 decimals")]
+    NotAnNft,
+    #[msg("This airdrop can only be claimed with a signed voucher")]
+    VoucherRequired,
+    #[msg("This airdrop does not use vouchers")]
+    NotAVoucherAirdrop,
+    #[msg("Voucher signature is missing or does not match")]
+    InvalidVoucher,
+    #[msg("Voucher has expired")]
+    VoucherExpired,
+    #[msg("Reputation score is out of range")]
+    ScoreOutOfRange,
+    #[msg("Badge is not held by its owner")]
+    BadgeHolderMismatch,
+    #[msg("Minimum score must not exceed the maximum")]
+    InvalidScoreRange,
+    #[msg("Tip reputation needs a daily cap")]
+    InvalidReputationSettings,
+    #[msg("Tip pair wallets must be distinct and in key order")]
+    InvalidTipPair,
+    #[msg("Badge was revoked and its cooldown has not passed")]
+    BadgeRevoked,
+    #[msg("Signer is not a registered attester")]
+    NotAnAttester,
+    #[msg("Attester registry is full")]
+    TooManyAttesters,
+    #[msg("A trust badge with a higher score is required")]
+    TrustScoreTooLow,
+    #[msg("Tier thresholds must increase and stay within the score range")]
+    InvalidTierThresholds,
+    #[msg("Escrow can't cover this payout and stay rent-exempt")]
+    InsufficientEscrowFunds,
+    #[msg("Giveaway was revealed or its claim deadline has not passed")]
+    GiveawayNotForfeited,
+    #[msg("Caller did not enter this giveaway")]
+    NotAnEntrant,
+    #[msg("Entrants still have shares of the forfeited prize to claim")]
+    PrizeForfeited,
+    #[msg("A match record for the sender is required to match this tip")]
+    MissingMatchRecord,
 }
@@ -0,0 +1,430 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, ErrorCode};
+
+/// Basis points denominator for `match_ratio_bps` (10_000 = 1:1)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Sponsor escrows `cap` to match tips to `beneficiary` (or to anyone when
+/// `None`) at `match_ratio_bps` until `expiry`. No single sender can draw
+/// more than `sender_cap` of matches.
+pub(crate) fn create_matching_pool(
+    ctx: Context<CreateMatchingPool>,
+    pool_id: u64,
+    beneficiary: Option<Pubkey>,
+    match_ratio_bps: u16,
+    cap: u64,
+    sender_cap: u64,
+    expiry: i64,
+) -> Result<()> {
+    require!(cap > 0, ErrorCode::InvalidAmount);
+    require!(sender_cap > 0, ErrorCode::InvalidAmount);
+    require!(match_ratio_bps > 0, ErrorCode::InvalidMatchRatio);
+    require!(expiry > Clock::get()?.unix_timestamp, ErrorCode::InvalidDeadline);
+
+    let pool = &mut ctx.accounts.matching_pool;
+    pool.sponsor = ctx.accounts.sponsor.key();
+    pool.pool_id = pool_id;
+    pool.beneficiary = beneficiary;
+    pool.match_ratio_bps = match_ratio_bps;
+    pool.cap = cap;
+    pool.sender_cap = sender_cap;
+    pool.matched = 0;
+    pool.expiry = expiry;
+    pool.bump = ctx.bumps.matching_pool;
+
+    match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.sponsor_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(sponsor_token_account), Some(token_program)) => {
+            pool.mint = Some(mint.key());
+
+            let cpi_accounts = Transfer {
+                from: sponsor_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.sponsor.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, cap)?;
+        }
+        (None, None, None, _) => {
+            pool.mint = None;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.sponsor.to_account_info(),
+                    to: ctx.accounts.matching_pool.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, cap)?;
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    msg!(
+        "Matching pool created: {} bps up to {} until {}",
+        match_ratio_bps,
+        cap,
+        expiry
+    );
+
+    Ok(())
+}
+
+/// Open the counter of matches `sender` has drawn from a pool
+pub(crate) fn open_match_record(ctx: Context<OpenMatchRecord>) -> Result<()> {
+    let match_record = &mut ctx.accounts.match_record;
+    match_record.matching_pool = ctx.accounts.matching_pool.key();
+    match_record.sender = ctx.accounts.sender.key();
+    match_record.matched = 0;
+    match_record.bump = ctx.bumps.match_record;
+    Ok(())
+}
+
+/// Sponsor takes back whatever is left once the pool has expired or been
+/// used up.
+pub(crate) fn close_matching_pool(ctx: Context<CloseMatchingPool>) -> Result<()> {
+    let pool = &ctx.accounts.matching_pool;
+    require!(
+        pool.remaining() == 0 || Clock::get()?.unix_timestamp >= pool.expiry,
+        ErrorCode::MatchingPoolActive
+    );
+
+    if pool.mint.is_some() {
+        let (Some(vault), Some(sponsor_token_account), Some(token_program)) = (
+            &ctx.accounts.vault,
+            &ctx.accounts.sponsor_token_account,
+            &ctx.accounts.token_program,
+        ) else {
+            return err!(ErrorCode::MissingTokenAccounts);
+        };
+
+        let pool_id = pool.pool_id.to_le_bytes();
+        let seeds = &[
+            b"matching-pool",
+            pool.sponsor.as_ref(),
+            pool_id.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        if vault.amount > 0 {
+            escrow::withdraw_tokens(
+                vault,
+                sponsor_token_account,
+                pool.to_account_info(),
+                signer,
+                token_program,
+                vault.amount,
+            )?;
+        }
+
+        escrow::close_vault(
+            vault,
+            ctx.accounts.sponsor.to_account_info(),
+            pool.to_account_info(),
+            signer,
+            token_program,
+        )?;
+    }
+
+    // Unused SOL returns with the account rent on close
+    msg!("Matching pool closed with {} matched", pool.matched);
+
+    Ok(())
+}
+
+/// Match a SOL tip out of the pool. Returns the matched amount, which is zero
+/// when the pool does not apply to this tip or the sender has used up their
+/// share. Self-tips are never matched.
+pub(crate) fn match_sol_tip<'info>(
+    pool: &mut Account<'info, MatchingPool>,
+    match_record: Option<&mut Account<'info, MatchRecord>>,
+    sender: Pubkey,
+    recipient: &AccountInfo<'info>,
+    tip_amount: u64,
+) -> Result<u64> {
+    if sender == recipient.key() {
+        return Ok(0);
+    }
+    let sender_matched = sender_matched(pool, match_record.as_deref(), sender)?;
+    let matched = pool.match_for(sender_matched, recipient.key(), None, tip_amount)?;
+    if matched == 0 {
+        return Ok(0);
+    }
+    let match_record = match_record.ok_or(ErrorCode::MissingMatchRecord)?;
+
+    escrow::withdraw_lamports(&pool.to_account_info(), recipient, matched)?;
+
+    record_match(pool, match_record, recipient.key(), tip_amount, matched)
+}
+
+/// Match an SPL tip out of the pool's vault into the recipient's token account
+pub(crate) fn match_spl_tip<'info>(
+    pool: &mut Account<'info, MatchingPool>,
+    match_record: Option<&mut Account<'info, MatchRecord>>,
+    vault: Option<&Account<'info, TokenAccount>>,
+    sender: Pubkey,
+    recipient_token_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    tip_amount: u64,
+) -> Result<u64> {
+    let recipient = recipient_token_account.owner;
+    if sender == recipient {
+        return Ok(0);
+    }
+    let sender_matched = sender_matched(pool, match_record.as_deref(), sender)?;
+    let matched = pool.match_for(
+        sender_matched,
+        recipient,
+        Some(recipient_token_account.mint),
+        tip_amount,
+    )?;
+    if matched == 0 {
+        return Ok(0);
+    }
+    let match_record = match_record.ok_or(ErrorCode::MissingMatchRecord)?;
+
+    let vault = vault.ok_or(ErrorCode::MissingTokenAccounts)?;
+    require_keys_eq!(vault.owner, pool.key(), ErrorCode::AccountMismatch);
+
+    let pool_id = pool.pool_id.to_le_bytes();
+    let seeds = &[
+        b"matching-pool",
+        pool.sponsor.as_ref(),
+        pool_id.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    escrow::withdraw_tokens(
+        vault,
+        recipient_token_account,
+        pool.to_account_info(),
+        signer,
+        token_program,
+        matched,
+    )?;
+
+    record_match(pool, match_record, recipient, tip_amount, matched)
+}
+
+/// Amount already matched for `sender` by `pool`. Without a record nothing
+/// has been, and one is only required once a match is actually paid.
+fn sender_matched(
+    pool: &Account<MatchingPool>,
+    match_record: Option<&Account<MatchRecord>>,
+    sender: Pubkey,
+) -> Result<u64> {
+    let Some(match_record) = match_record else {
+        return Ok(0);
+    };
+    require!(match_record.is_for(pool.key(), sender), ErrorCode::AccountMismatch);
+    Ok(match_record.matched)
+}
+
+fn record_match(
+    pool: &mut Account<MatchingPool>,
+    match_record: &mut Account<MatchRecord>,
+    recipient: Pubkey,
+    tip_amount: u64,
+    matched: u64,
+) -> Result<u64> {
+    pool.matched = pool.matched
+        .checked_add(matched)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    match_record.matched = match_record.matched
+        .checked_add(matched)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(MatchEvent {
+        matching_pool: pool.key(),
+        sponsor: pool.sponsor,
+        sender: match_record.sender,
+        recipient,
+        tip_amount,
+        matched_amount: matched,
+        remaining: pool.remaining(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(matched)
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreateMatchingPool<'info> {
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + MatchingPool::INIT_SPACE,
+        seeds = [b"matching-pool", sponsor.key().as_ref(), &pool_id.to_le_bytes()],
+        bump
+    )]
+    pub matching_pool: Account<'info, MatchingPool>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// Only for SPL pools
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL pools
+    #[account(
+        init,
+        payer = sponsor,
+        seeds = [b"matching-vault", matching_pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = matching_pool,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL pools
+    #[account(mut)]
+    pub sponsor_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenMatchRecord<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MatchRecord::INIT_SPACE,
+        seeds = [b"match-record", matching_pool.key().as_ref(), sender.key().as_ref()],
+        bump
+    )]
+    pub match_record: Account<'info, MatchRecord>,
+
+    pub matching_pool: Account<'info, MatchingPool>,
+
+    /// CHECK: Tip sender the record counts matches for
+    pub sender: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMatchingPool<'info> {
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = sponsor @ ErrorCode::Unauthorized,
+        seeds = [b"matching-pool", matching_pool.sponsor.as_ref(), &matching_pool.pool_id.to_le_bytes()],
+        bump = matching_pool.bump,
+    )]
+    pub matching_pool: Account<'info, MatchingPool>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// Only for SPL pools
+    #[account(
+        mut,
+        seeds = [b"matching-vault", matching_pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL pools
+    #[account(mut)]
+    pub sponsor_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct MatchingPool {
+    pub sponsor: Pubkey,              // 32
+    pub pool_id: u64,                 // 8
+    pub beneficiary: Option<Pubkey>,  // 1 + 32 (None matches any recipient)
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub match_ratio_bps: u16,         // 2
+    pub cap: u64,                     // 8
+    pub sender_cap: u64,              // 8
+    pub matched: u64,                 // 8
+    pub expiry: i64,                  // 8
+    pub bump: u8,                     // 1
+}
+
+impl MatchingPool {
+    /// Cap left to match
+    pub fn remaining(&self) -> u64 {
+        self.cap.saturating_sub(self.matched)
+    }
+
+    /// Amount this pool would add to a tip of `tip_amount` to `recipient` in
+    /// `mint` from a sender already matched `sender_matched`, or zero if the
+    /// tip does not qualify. Matches are limited by what is left of both the
+    /// pool and the sender's share.
+    pub fn match_for(
+        &self,
+        sender_matched: u64,
+        recipient: Pubkey,
+        mint: Option<Pubkey>,
+        tip_amount: u64,
+    ) -> Result<u64> {
+        if self.mint != mint
+            || self.beneficiary.is_some_and(|b| b != recipient)
+            || Clock::get()?.unix_timestamp >= self.expiry
+        {
+            return Ok(0);
+        }
+
+        let matched = (tip_amount as u128)
+            .checked_mul(self.match_ratio_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / BPS_DENOMINATOR as u128;
+        Ok(u64::try_from(matched)
+            .unwrap_or(u64::MAX)
+            .min(self.remaining())
+            .min(self.sender_cap.saturating_sub(sender_matched)))
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MatchRecord {
+    pub matching_pool: Pubkey,        // 32
+    pub sender: Pubkey,               // 32
+    pub matched: u64,                 // 8
+    pub bump: u8,                     // 1
+}
+
+impl MatchRecord {
+    /// Whether this record counts matches for `sender` from `matching_pool`
+    pub fn is_for(&self, matching_pool: Pubkey, sender: Pubkey) -> bool {
+        (self.matching_pool, self.sender) == (matching_pool, sender)
+    }
+}
+
+// Events
+
+#[event]
+pub struct MatchEvent {
+    pub matching_pool: Pubkey,
+    pub sponsor: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub tip_amount: u64,
+    pub matched_amount: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
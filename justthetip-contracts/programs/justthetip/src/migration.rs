@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{ErrorCode, UserAccount};
+
+/// Grow a user account created before the referral and rate limit fields
+/// existed to the current layout. The new bytes are zeroed, which reads as
+/// no referrer, no earnings and an empty rate window. The payer covers the
+/// extra rent. Accounts already at the current size are left alone.
+pub(crate) fn migrate_user_account(ctx: Context<MigrateUserAccount>, _discord_id: String) -> Result<()> {
+    let user_account = ctx.accounts.user_account.to_account_info();
+    require!(
+        user_account.data.borrow().starts_with(&UserAccount::DISCRIMINATOR),
+        ErrorCode::AccountMismatch
+    );
+
+    let new_len = 8 + UserAccount::INIT_SPACE;
+    if user_account.data_len() >= new_len {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?.minimum_balance(new_len);
+    let top_up = rent.saturating_sub(user_account.lamports());
+    if top_up > 0 {
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: user_account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, top_up)?;
+    }
+    user_account.realloc(new_len, true)?;
+
+    msg!("User account migrated to {} bytes", new_len);
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(discord_id: String)]
+pub struct MigrateUserAccount<'info> {
+    /// CHECK: May still have the old layout, so it can't be deserialized
+    /// yet; checked by seeds, owner and discriminator
+    #[account(
+        mut,
+        seeds = [b"user", discord_id.as_bytes()],
+        bump,
+        owner = crate::ID @ ErrorCode::AccountMismatch,
+    )]
+    pub user_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
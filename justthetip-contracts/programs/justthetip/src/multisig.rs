@@ -0,0 +1,522 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::{attester, config, escrow, AttesterRegistry, Config, ConfigParams, ErrorCode};
+
+/// Maximum number of multisig signers
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
+
+/// Set up the M-of-N signer set and hand the config admin role to it. From
+/// then on config changes are queued through approved proposals.
+pub(crate) fn initialize_multisig(
+    ctx: Context<InitializeMultisig>,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+    proposal_lifetime: i64,
+) -> Result<()> {
+    validate_signer_set(&signers, threshold)?;
+    require!(proposal_lifetime > 0, ErrorCode::InvalidInterval);
+
+    let multisig = &mut ctx.accounts.multisig;
+    multisig.signers = signers;
+    multisig.threshold = threshold;
+    multisig.proposal_lifetime = proposal_lifetime;
+    multisig.proposal_count = 0;
+    multisig.bump = ctx.bumps.multisig;
+
+    ctx.accounts.config.admin = multisig.key();
+
+    msg!(
+        "Multisig initialized: {} of {} signers",
+        threshold,
+        multisig.signers.len()
+    );
+    Ok(())
+}
+
+/// A signer proposes an action. The proposer's approval is counted.
+pub(crate) fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+    match &action {
+        ProposalAction::WithdrawTreasury { amount, .. } => {
+            require!(*amount > 0, ErrorCode::InvalidAmount)
+        }
+        ProposalAction::CreateAirdrop { total_amount, .. } => {
+            require!(*total_amount > 0, ErrorCode::InvalidAmount)
+        }
+        ProposalAction::UpdateConfig { params } => params.validate()?,
+        ProposalAction::CancelConfigChange => {}
+        ProposalAction::UpdateMultisig { signers, threshold } => {
+            validate_signer_set(signers, *threshold)?
+        }
+        ProposalAction::SetAttester { .. } => {}
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let multisig = &mut ctx.accounts.multisig;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.multisig = multisig.key();
+    proposal.proposal_id = multisig.proposal_count;
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.action = action;
+    proposal.approvals = vec![ctx.accounts.proposer.key()];
+    proposal.status = ProposalStatus::Pending;
+    proposal.created_at = now;
+    proposal.expires_at = now
+        .checked_add(multisig.proposal_lifetime)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    proposal.bump = ctx.bumps.proposal;
+
+    multisig.proposal_count = multisig.proposal_count
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(ProposalCreated {
+        proposal: proposal.key(),
+        proposal_id: proposal.proposal_id,
+        proposer: proposal.proposer,
+        action: proposal.action.clone(),
+        expires_at: proposal.expires_at,
+    });
+
+    Ok(())
+}
+
+/// A signer approves a pending proposal. Approvals from keys rotated out of
+/// the signer set are dropped first, so the list never outgrows the signers.
+pub(crate) fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let approver = ctx.accounts.approver.key();
+    let multisig = &ctx.accounts.multisig;
+    let proposal = &mut ctx.accounts.proposal;
+    require!(
+        proposal.status == ProposalStatus::Pending,
+        ErrorCode::ProposalNotPending
+    );
+    require!(now < proposal.expires_at, ErrorCode::ProposalExpired);
+    require!(
+        !proposal.approvals.contains(&approver),
+        ErrorCode::AlreadyApproved
+    );
+    proposal.approvals.retain(|key| multisig.is_signer(key));
+    proposal.approvals.push(approver);
+
+    emit!(ProposalApproved {
+        proposal: proposal.key(),
+        approver,
+        approvals: multisig.approvals_for(proposal)?,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// A signer executes a proposal once enough current signers have approved it
+pub(crate) fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let proposal = &ctx.accounts.proposal;
+    require!(
+        proposal.status == ProposalStatus::Pending,
+        ErrorCode::ProposalNotPending
+    );
+    require!(now < proposal.expires_at, ErrorCode::ProposalExpired);
+    require!(
+        ctx.accounts.multisig.approvals_for(proposal)? >= ctx.accounts.multisig.threshold,
+        ErrorCode::ThresholdNotMet
+    );
+
+    match proposal.action.clone() {
+        ProposalAction::WithdrawTreasury { mint, amount, destination } => {
+            let destination_info = ctx
+                .accounts
+                .destination
+                .as_ref()
+                .ok_or(ErrorCode::MissingTokenAccounts)?;
+            require_keys_eq!(destination_info.key(), destination, ErrorCode::AccountMismatch);
+            withdraw_treasury(ctx.accounts, mint, amount, destination_info)?;
+        }
+        ProposalAction::UpdateConfig { params } => {
+            let multisig = ctx.accounts.multisig.key();
+            let config = ctx
+                .accounts
+                .config
+                .as_mut()
+                .ok_or(ErrorCode::AccountMismatch)?;
+            config::queue_change(config, params, multisig)?;
+        }
+        ProposalAction::CancelConfigChange => {
+            let multisig = ctx.accounts.multisig.key();
+            let config = ctx
+                .accounts
+                .config
+                .as_mut()
+                .ok_or(ErrorCode::AccountMismatch)?;
+            config::cancel_change(config, multisig)?;
+        }
+        ProposalAction::UpdateMultisig { signers, threshold } => {
+            let multisig = &mut ctx.accounts.multisig;
+            multisig.signers = signers;
+            multisig.threshold = threshold;
+        }
+        ProposalAction::SetAttester { attester, weight } => {
+            let registry = ctx
+                .accounts
+                .attester_registry
+                .as_mut()
+                .ok_or(ErrorCode::AccountMismatch)?;
+            attester::set_attester_weight(registry, attester, weight)?;
+        }
+        // Approval only; spent by `create_airdrop`
+        ProposalAction::CreateAirdrop { .. } => {}
+    }
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.status = ProposalStatus::Executed;
+
+    emit!(ProposalExecuted {
+        proposal: proposal.key(),
+        executor: ctx.accounts.executor.key(),
+        action: proposal.action.clone(),
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Proposer reclaims the rent of a proposal that is finished or has expired
+pub(crate) fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+    let proposal = &ctx.accounts.proposal;
+    require!(
+        proposal.status != ProposalStatus::Pending
+            || Clock::get()?.unix_timestamp >= proposal.expires_at,
+        ErrorCode::ProposalActive
+    );
+
+    msg!("Proposal {} closed", proposal.proposal_id);
+    Ok(())
+}
+
+/// Spend an executed airdrop approval. Called by `create_airdrop` for
+/// airdrops at or above the config threshold.
+pub(crate) fn consume_airdrop_approval(
+    approval: Option<&mut Account<Proposal>>,
+    creator: Pubkey,
+    total_amount: u64,
+    recipients_count: u8,
+) -> Result<()> {
+    let approval = approval.ok_or(ErrorCode::ApprovalRequired)?;
+    require!(
+        approval.status == ProposalStatus::Executed
+            && Clock::get()?.unix_timestamp < approval.expires_at
+            && approval.action
+                == (ProposalAction::CreateAirdrop {
+                    creator,
+                    total_amount,
+                    recipients_count,
+                }),
+        ErrorCode::ApprovalRequired
+    );
+    approval.status = ProposalStatus::Consumed;
+    Ok(())
+}
+
+fn withdraw_treasury<'info>(
+    accounts: &ExecuteProposal<'info>,
+    mint: Option<Pubkey>,
+    amount: u64,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    let multisig = &accounts.multisig;
+    match mint {
+        Some(mint) => {
+            let (Some(treasury_token_account), Some(destination_token_account), Some(token_program)) = (
+                &accounts.treasury_token_account,
+                &accounts.destination_token_account,
+                &accounts.token_program,
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(treasury_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(destination_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(
+                treasury_token_account.owner,
+                multisig.key(),
+                ErrorCode::AccountMismatch
+            );
+            require_keys_eq!(
+                destination_token_account.owner,
+                destination.key(),
+                ErrorCode::AccountMismatch
+            );
+
+            let seeds = &[b"multisig".as_ref(), &[multisig.bump]];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                treasury_token_account,
+                destination_token_account,
+                multisig.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )
+        }
+        None => escrow::withdraw_lamports(&multisig.to_account_info(), destination, amount),
+    }
+}
+
+fn validate_signer_set(signers: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+        ErrorCode::InvalidSignerSet
+    );
+    for (i, signer) in signers.iter().enumerate() {
+        require!(!signers[..i].contains(signer), ErrorCode::InvalidSignerSet);
+    }
+    require!(
+        threshold > 0 && threshold as usize <= signers.len(),
+        ErrorCode::InvalidThreshold
+    );
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct InitializeMultisig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Multisig::INIT_SPACE,
+        seeds = [b"multisig"],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&proposer.key()) @ ErrorCode::NotASigner,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", multisig.key().as_ref(), &multisig.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&approver.key()) @ ErrorCode::NotASigner,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        has_one = multisig @ ErrorCode::AccountMismatch,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&executor.key()) @ ErrorCode::NotASigner,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        has_one = multisig @ ErrorCode::AccountMismatch,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub executor: Signer<'info>,
+
+    /// Only for config changes
+    #[account(
+        mut,
+        constraint = config.admin == multisig.key() @ ErrorCode::Unauthorized,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, Config>>,
+
+    /// Only for attester changes
+    #[account(mut, seeds = [b"attesters"], bump = attester_registry.bump)]
+    pub attester_registry: Option<Account<'info, AttesterRegistry>>,
+
+    /// CHECK: Only for treasury withdrawals; must match the proposal destination
+    #[account(mut)]
+    pub destination: Option<AccountInfo<'info>>,
+
+    /// Only for SPL treasury withdrawals: token account owned by the multisig
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL treasury withdrawals
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer @ ErrorCode::Unauthorized,
+        seeds = [b"proposal", proposal.multisig.as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    #[max_len(10)]
+    pub signers: Vec<Pubkey>,         // 4 + 10 * 32
+    pub threshold: u8,                // 1
+    pub proposal_lifetime: i64,       // 8
+    pub proposal_count: u64,          // 8
+    pub bump: u8,                     // 1
+}
+
+impl Multisig {
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    /// Approvals on `proposal` from keys that are still signers
+    pub fn approvals_for(&self, proposal: &Proposal) -> Result<u8> {
+        let approvals = proposal
+            .approvals
+            .iter()
+            .filter(|key| self.is_signer(key))
+            .count();
+        u8::try_from(approvals).map_err(|_| error!(ErrorCode::ArithmeticOverflow))
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub multisig: Pubkey,             // 32
+    pub proposal_id: u64,             // 8
+    pub proposer: Pubkey,             // 32
+    pub action: ProposalAction,       // 1 + 583
+    #[max_len(10)]
+    pub approvals: Vec<Pubkey>,       // 4 + 10 * 32
+    pub status: ProposalStatus,       // 1
+    pub created_at: i64,              // 8
+    pub expires_at: i64,              // 8
+    pub bump: u8,                     // 1
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum ProposalAction {
+    /// Move funds out of the multisig-owned treasury
+    WithdrawTreasury {
+        mint: Option<Pubkey>,
+        amount: u64,
+        destination: Pubkey,
+    },
+    /// Allow `creator` to create an airdrop above the config threshold
+    CreateAirdrop {
+        creator: Pubkey,
+        total_amount: u64,
+        recipients_count: u8,
+    },
+    /// Queue a config change, subject to the config timelock
+    UpdateConfig { params: ConfigParams },
+    /// Drop the queued config change
+    CancelConfigChange,
+    /// Replace the signer set and threshold
+    UpdateMultisig {
+        #[max_len(10)]
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    },
+    /// Register, reweight or (with weight 0) remove a trust badge attester
+    SetAttester { attester: Pubkey, weight: u16 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ProposalStatus {
+    Pending,
+    Executed,
+    /// An executed airdrop approval that has been used
+    Consumed,
+}
+
+// Events
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal: Pubkey,
+    pub executor: Pubkey,
+    pub action: ProposalAction,
+    pub timestamp: i64,
+}
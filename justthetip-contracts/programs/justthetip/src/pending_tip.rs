@@ -0,0 +1,408 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{
+    escrow, trust_badge, Config, ErrorCode, TipEvent, TipStatus, TokenType, TrustBadge, UserAccount,
+};
+
+/// Longest cancellation window a sender may choose (7 days)
+pub const MAX_CANCEL_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+/// Hold a tip in escrow. The recipient can claim it at any time; the sender
+/// can cancel it until it is claimed or `cancel_window` seconds have passed.
+pub(crate) fn send_escrowed_tip(
+    ctx: Context<SendEscrowedTip>,
+    tip_id: u64,
+    amount: u64,
+    cancel_window: i64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        cancel_window > 0 && cancel_window <= MAX_CANCEL_WINDOW,
+        ErrorCode::InvalidCancelWindow
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+
+    // Large tips need a trusted sender
+    let mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+    trust_badge::require_trust_score(
+        ctx.accounts.sender_badge.as_deref_mut(),
+        ctx.accounts.sender_badge_token_account.as_deref(),
+        &ctx.accounts.config,
+        ctx.accounts.config.tip_min_score(mint, amount),
+        now,
+    )?;
+
+    let pending_tip = &mut ctx.accounts.pending_tip;
+    pending_tip.sender = ctx.accounts.sender.key();
+    pending_tip.recipient = ctx.accounts.recipient_account.authority;
+    pending_tip.tip_id = tip_id;
+    pending_tip.amount = amount;
+    pending_tip.created_at = now;
+    pending_tip.cancel_deadline = now
+        .checked_add(cancel_window)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    pending_tip.bump = ctx.bumps.pending_tip;
+
+    let token_type = match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.sender_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(sender_token_account), Some(token_program)) => {
+            pending_tip.mint = Some(mint.key());
+
+            let cpi_accounts = Transfer {
+                from: sender_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)?;
+            TokenType::SplToken
+        }
+        (None, None, None, _) => {
+            pending_tip.mint = None;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.pending_tip.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+            TokenType::Sol
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    };
+
+    emit!(TipEvent {
+        sender: ctx.accounts.pending_tip.sender,
+        recipient: ctx.accounts.pending_tip.recipient,
+        amount,
+        token_type,
+        status: TipStatus::Sent,
+        timestamp: now,
+    });
+
+    msg!(
+        "Escrowed tip sent: {} from {} to {}, cancellable until {}",
+        amount,
+        ctx.accounts.pending_tip.sender,
+        ctx.accounts.pending_tip.recipient,
+        ctx.accounts.pending_tip.cancel_deadline
+    );
+
+    Ok(())
+}
+
+/// Recipient claims an escrowed tip
+pub(crate) fn claim_tip(ctx: Context<ClaimTip>) -> Result<()> {
+    let pending_tip = &ctx.accounts.pending_tip;
+    let amount = pending_tip.amount;
+
+    let token_type = release(
+        pending_tip,
+        &ctx.accounts.recipient.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        ctx.accounts.vault.as_ref(),
+        ctx.accounts.destination_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    // Update sender stats
+    let sender_account = &mut ctx.accounts.sender_account;
+    sender_account.total_sent = sender_account.total_sent
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    sender_account.tip_count = sender_account.tip_count
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    // Update recipient stats
+    let recipient_account = &mut ctx.accounts.recipient_account;
+    recipient_account.total_received = recipient_account.total_received
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(TipEvent {
+        sender: ctx.accounts.pending_tip.sender,
+        recipient: ctx.accounts.pending_tip.recipient,
+        amount,
+        token_type,
+        status: TipStatus::Claimed,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Escrowed tip claimed: {} by {}", amount, ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Sender cancels an unclaimed escrowed tip inside its cancellation window
+pub(crate) fn cancel_tip(ctx: Context<CancelTip>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pending_tip = &ctx.accounts.pending_tip;
+    require!(now < pending_tip.cancel_deadline, ErrorCode::CancelWindowClosed);
+
+    let token_type = release(
+        pending_tip,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        ctx.accounts.vault.as_ref(),
+        ctx.accounts.destination_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    emit!(TipEvent {
+        sender: pending_tip.sender,
+        recipient: pending_tip.recipient,
+        amount: pending_tip.amount,
+        token_type,
+        status: TipStatus::Cancelled,
+        timestamp: now,
+    });
+
+    msg!("Escrowed tip cancelled: {} returned to {}", pending_tip.amount, pending_tip.sender);
+
+    Ok(())
+}
+
+/// Move the escrowed amount to `destination`. SPL vaults are emptied and
+/// their rent returned to `rent_destination`; the PDA itself is closed by
+/// the instruction's `close` constraint.
+fn release<'info>(
+    pending_tip: &Account<'info, PendingTip>,
+    destination: &AccountInfo<'info>,
+    rent_destination: &AccountInfo<'info>,
+    vault: Option<&Account<'info, TokenAccount>>,
+    destination_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+) -> Result<TokenType> {
+    match pending_tip.mint {
+        Some(mint) => {
+            let (Some(vault), Some(destination_token_account), Some(token_program)) =
+                (vault, destination_token_account, token_program)
+            else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(destination_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(
+                destination_token_account.owner,
+                destination.key(),
+                ErrorCode::AccountMismatch
+            );
+
+            let tip_id = pending_tip.tip_id.to_le_bytes();
+            let seeds = &[
+                b"pending-tip",
+                pending_tip.sender.as_ref(),
+                pending_tip.recipient.as_ref(),
+                tip_id.as_ref(),
+                &[pending_tip.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                vault,
+                destination_token_account,
+                pending_tip.to_account_info(),
+                signer,
+                token_program,
+                pending_tip.amount,
+            )?;
+
+            escrow::close_vault(
+                vault,
+                rent_destination.clone(),
+                pending_tip.to_account_info(),
+                signer,
+                token_program,
+            )?;
+            Ok(TokenType::SplToken)
+        }
+        None => {
+            escrow::withdraw_lamports(&pending_tip.to_account_info(), destination, pending_tip.amount)?;
+            Ok(TokenType::Sol)
+        }
+    }
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(tip_id: u64)]
+pub struct SendEscrowedTip<'info> {
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + PendingTip::INIT_SPACE,
+        seeds = [
+            b"pending-tip",
+            sender.key().as_ref(),
+            recipient_account.authority.as_ref(),
+            &tip_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub pending_tip: Account<'info, PendingTip>,
+
+    #[account(
+        seeds = [b"user", sender_account.discord_id.as_bytes()],
+        bump = sender_account.bump,
+        constraint = sender_account.authority == sender.key() @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_account: Account<'info, UserAccount>,
+
+    #[account(
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Sender's trust badge, required for large tips
+    #[account(
+        mut,
+        seeds = [b"trust-badge", sender.key().as_ref()],
+        bump = sender_badge.bump,
+    )]
+    pub sender_badge: Option<Box<Account<'info, TrustBadge>>>,
+
+    /// Sender's badge token account, required with `sender_badge` for gated tips
+    pub sender_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Only for SPL tips
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL tips
+    #[account(
+        init,
+        payer = sender,
+        seeds = [b"pending-tip-vault", pending_tip.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pending_tip,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL tips
+    #[account(mut)]
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTip<'info> {
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender @ ErrorCode::AccountMismatch,
+        has_one = recipient @ ErrorCode::Unauthorized,
+        seeds = [
+            b"pending-tip",
+            pending_tip.sender.as_ref(),
+            pending_tip.recipient.as_ref(),
+            &pending_tip.tip_id.to_le_bytes(),
+        ],
+        bump = pending_tip.bump,
+    )]
+    pub pending_tip: Account<'info, PendingTip>,
+
+    #[account(
+        mut,
+        seeds = [b"user", sender_account.discord_id.as_bytes()],
+        bump = sender_account.bump,
+        constraint = sender_account.authority == pending_tip.sender @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_account: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+        constraint = recipient_account.authority == pending_tip.recipient @ ErrorCode::AccountMismatch,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// CHECK: Receives the escrow rent; must match the tip sender
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+
+    /// Only for SPL tips
+    #[account(
+        mut,
+        seeds = [b"pending-tip-vault", pending_tip.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL tips: the recipient's token account
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTip<'info> {
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender @ ErrorCode::Unauthorized,
+        seeds = [
+            b"pending-tip",
+            pending_tip.sender.as_ref(),
+            pending_tip.recipient.as_ref(),
+            &pending_tip.tip_id.to_le_bytes(),
+        ],
+        bump = pending_tip.bump,
+    )]
+    pub pending_tip: Account<'info, PendingTip>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Only for SPL tips
+    #[account(
+        mut,
+        seeds = [b"pending-tip-vault", pending_tip.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL tips: the sender's token account
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct PendingTip {
+    pub sender: Pubkey,               // 32
+    pub recipient: Pubkey,            // 32
+    pub tip_id: u64,                  // 8
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub amount: u64,                  // 8
+    pub created_at: i64,              // 8
+    pub cancel_deadline: i64,         // 8
+    pub bump: u8,                     // 1
+}
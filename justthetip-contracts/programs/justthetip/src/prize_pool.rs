@@ -0,0 +1,487 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, ErrorCode};
+
+/// Most winners a single pool can pay out
+pub const MAX_PRIZE_WINNERS: usize = 25;
+
+/// Host funds a prize pool up front and names the key allowed to settle it
+pub(crate) fn create_prize_pool(
+    ctx: Context<CreatePrizePool>,
+    pool_id: u64,
+    amount: u64,
+    settlement_authority: Pubkey,
+    claim_deadline: i64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        claim_deadline > Clock::get()?.unix_timestamp,
+        ErrorCode::InvalidDeadline
+    );
+
+    let pool = &mut ctx.accounts.prize_pool;
+    pool.host = ctx.accounts.host.key();
+    pool.settlement_authority = settlement_authority;
+    pool.pool_id = pool_id;
+    pool.amount = amount;
+    pool.claimed_amount = 0;
+    pool.claim_deadline = claim_deadline;
+    pool.settled = false;
+    pool.winners = Vec::new();
+    pool.bump = ctx.bumps.prize_pool;
+
+    match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.host_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(host_token_account), Some(token_program)) => {
+            pool.mint = Some(mint.key());
+
+            let cpi_accounts = Transfer {
+                from: host_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.host.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)?;
+        }
+        (None, None, None, _) => {
+            pool.mint = None;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.host.to_account_info(),
+                    to: ctx.accounts.prize_pool.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    let pool = &ctx.accounts.prize_pool;
+    emit!(PrizePoolCreated {
+        prize_pool: pool.key(),
+        host: pool.host,
+        settlement_authority,
+        mint: pool.mint,
+        amount,
+        claim_deadline,
+    });
+
+    Ok(())
+}
+
+/// Settlement key records the winners. Anything not allocated to a winner is
+/// returned to the host straight away.
+pub(crate) fn settle_prize_pool(
+    ctx: Context<SettlePrizePool>,
+    payouts: Vec<PrizePayout>,
+) -> Result<()> {
+    let pool = &ctx.accounts.prize_pool;
+    require!(!pool.settled, ErrorCode::PoolAlreadySettled);
+    require!(
+        Clock::get()?.unix_timestamp < pool.claim_deadline,
+        ErrorCode::ClaimDeadlinePassed
+    );
+    require!(
+        !payouts.is_empty() && payouts.len() <= MAX_PRIZE_WINNERS,
+        ErrorCode::TooManyWinners
+    );
+
+    let mut allocated: u64 = 0;
+    for (i, payout) in payouts.iter().enumerate() {
+        require!(payout.amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            !payouts[..i].iter().any(|p| p.winner == payout.winner),
+            ErrorCode::DuplicateWinner
+        );
+        allocated = allocated
+            .checked_add(payout.amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    require!(allocated <= pool.amount, ErrorCode::PayoutsExceedPool);
+
+    let leftover = pool.amount - allocated;
+    if leftover > 0 {
+        pay_out(
+            pool,
+            &ctx.accounts.host.to_account_info(),
+            ctx.accounts.vault.as_ref(),
+            ctx.accounts.host_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            leftover,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.prize_pool;
+    pool.amount = allocated;
+    pool.settled = true;
+    pool.winners = payouts
+        .iter()
+        .map(|p| PrizeWinner {
+            winner: p.winner,
+            amount: p.amount,
+            claimed: false,
+        })
+        .collect();
+
+    emit!(PrizePoolSettled {
+        prize_pool: pool.key(),
+        winners: payouts.len() as u8,
+        allocated,
+        refunded: leftover,
+    });
+
+    Ok(())
+}
+
+/// A settled winner claims their prize before the claim deadline. After it,
+/// unclaimed prizes belong to the host.
+pub(crate) fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+    let pool = &ctx.accounts.prize_pool;
+    require!(pool.settled, ErrorCode::PoolNotSettled);
+    require!(
+        Clock::get()?.unix_timestamp < pool.claim_deadline,
+        ErrorCode::ClaimDeadlinePassed
+    );
+
+    let winner = ctx.accounts.winner.key();
+    let index = pool
+        .winners
+        .iter()
+        .position(|w| w.winner == winner)
+        .ok_or(ErrorCode::NotAWinner)?;
+    require!(!pool.winners[index].claimed, ErrorCode::PrizeAlreadyClaimed);
+    let amount = pool.winners[index].amount;
+
+    pay_out(
+        pool,
+        &ctx.accounts.winner.to_account_info(),
+        ctx.accounts.vault.as_ref(),
+        ctx.accounts.winner_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+        amount,
+    )?;
+
+    let pool = &mut ctx.accounts.prize_pool;
+    pool.winners[index].claimed = true;
+    pool.claimed_amount = pool.claimed_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(PrizeClaimed {
+        prize_pool: pool.key(),
+        winner,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Host closes the pool once every prize is claimed or the claim deadline
+/// has passed, taking back whatever is left.
+pub(crate) fn close_prize_pool(ctx: Context<ClosePrizePool>) -> Result<()> {
+    let pool = &ctx.accounts.prize_pool;
+    let all_claimed = pool.settled && pool.winners.iter().all(|w| w.claimed);
+    require!(
+        all_claimed || Clock::get()?.unix_timestamp >= pool.claim_deadline,
+        ErrorCode::PoolStillClaimable
+    );
+
+    let remaining = pool.amount
+        .checked_sub(pool.claimed_amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    if pool.mint.is_some() {
+        let (Some(vault), Some(token_program)) =
+            (&ctx.accounts.vault, &ctx.accounts.token_program)
+        else {
+            return err!(ErrorCode::MissingTokenAccounts);
+        };
+
+        if remaining > 0 {
+            pay_out(
+                pool,
+                &ctx.accounts.host.to_account_info(),
+                Some(vault),
+                ctx.accounts.host_token_account.as_ref(),
+                Some(token_program),
+                remaining,
+            )?;
+        }
+
+        let pool_id = pool.pool_id.to_le_bytes();
+        let seeds = &[
+            b"prize-pool",
+            pool.host.as_ref(),
+            pool_id.as_ref(),
+            &[pool.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        escrow::close_vault(
+            vault,
+            ctx.accounts.host.to_account_info(),
+            pool.to_account_info(),
+            signer,
+            token_program,
+        )?;
+    }
+
+    // SOL left in the pool returns with the account rent on close
+    emit!(PrizePoolClosed {
+        prize_pool: pool.key(),
+        host: pool.host,
+        refunded: remaining,
+    });
+
+    Ok(())
+}
+
+/// Move `amount` out of the pool escrow to `destination`
+fn pay_out<'info>(
+    pool: &Account<'info, PrizePool>,
+    destination: &AccountInfo<'info>,
+    vault: Option<&Account<'info, TokenAccount>>,
+    destination_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+) -> Result<()> {
+    match pool.mint {
+        Some(mint) => {
+            let (Some(vault), Some(destination_token_account), Some(token_program)) =
+                (vault, destination_token_account, token_program)
+            else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(destination_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(
+                destination_token_account.owner,
+                destination.key(),
+                ErrorCode::AccountMismatch
+            );
+
+            let pool_id = pool.pool_id.to_le_bytes();
+            let seeds = &[
+                b"prize-pool",
+                pool.host.as_ref(),
+                pool_id.as_ref(),
+                &[pool.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                vault,
+                destination_token_account,
+                pool.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )
+        }
+        None => {
+            escrow::withdraw_lamports(&pool.to_account_info(), destination, amount)
+        }
+    }
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreatePrizePool<'info> {
+    #[account(
+        init,
+        payer = host,
+        space = 8 + PrizePool::INIT_SPACE,
+        seeds = [b"prize-pool", host.key().as_ref(), &pool_id.to_le_bytes()],
+        bump
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// Only for SPL prize pools
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL prize pools
+    #[account(
+        init,
+        payer = host,
+        seeds = [b"prize-pool-vault", prize_pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = prize_pool,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL prize pools
+    #[account(mut)]
+    pub host_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePrizePool<'info> {
+    #[account(
+        mut,
+        has_one = host @ ErrorCode::AccountMismatch,
+        has_one = settlement_authority @ ErrorCode::Unauthorized,
+        seeds = [b"prize-pool", prize_pool.host.as_ref(), &prize_pool.pool_id.to_le_bytes()],
+        bump = prize_pool.bump,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    pub settlement_authority: Signer<'info>,
+
+    /// CHECK: Receives unallocated funds; must match the pool host
+    #[account(mut)]
+    pub host: AccountInfo<'info>,
+
+    /// Only for SPL prize pools
+    #[account(
+        mut,
+        seeds = [b"prize-pool-vault", prize_pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL prize pools
+    #[account(mut)]
+    pub host_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"prize-pool", prize_pool.host.as_ref(), &prize_pool.pool_id.to_le_bytes()],
+        bump = prize_pool.bump,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    #[account(mut)]
+    pub winner: Signer<'info>,
+
+    /// Only for SPL prize pools
+    #[account(
+        mut,
+        seeds = [b"prize-pool-vault", prize_pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL prize pools
+    #[account(mut)]
+    pub winner_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePrizePool<'info> {
+    #[account(
+        mut,
+        close = host,
+        has_one = host @ ErrorCode::Unauthorized,
+        seeds = [b"prize-pool", prize_pool.host.as_ref(), &prize_pool.pool_id.to_le_bytes()],
+        bump = prize_pool.bump,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// Only for SPL prize pools
+    #[account(
+        mut,
+        seeds = [b"prize-pool-vault", prize_pool.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL prize pools
+    #[account(mut)]
+    pub host_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct PrizePool {
+    pub host: Pubkey,                 // 32
+    pub settlement_authority: Pubkey, // 32
+    pub pool_id: u64,                 // 8
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub amount: u64,                  // 8 (allocated total once settled)
+    pub claimed_amount: u64,          // 8
+    pub claim_deadline: i64,          // 8
+    pub settled: bool,                // 1
+    #[max_len(MAX_PRIZE_WINNERS)]
+    pub winners: Vec<PrizeWinner>,    // 4 + 25 * 41
+    pub bump: u8,                     // 1
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PrizeWinner {
+    pub winner: Pubkey,
+    pub amount: u64,
+    pub claimed: bool,
+}
+
+/// Winner entry submitted by the settlement key
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PrizePayout {
+    pub winner: Pubkey,
+    pub amount: u64,
+}
+
+// Events
+
+#[event]
+pub struct PrizePoolCreated {
+    pub prize_pool: Pubkey,
+    pub host: Pubkey,
+    pub settlement_authority: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub claim_deadline: i64,
+}
+
+#[event]
+pub struct PrizePoolSettled {
+    pub prize_pool: Pubkey,
+    pub winners: u8,
+    pub allocated: u64,
+    pub refunded: u64,
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub prize_pool: Pubkey,
+    pub winner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrizePoolClosed {
+    pub prize_pool: Pubkey,
+    pub host: Pubkey,
+    pub refunded: u64,
+}
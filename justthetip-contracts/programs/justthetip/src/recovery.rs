@@ -0,0 +1,332 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, UserAccount};
+
+/// Maximum number of guardians per user
+pub const MAX_GUARDIANS: usize = 10;
+/// Shortest delay a user can choose before a recovery takes effect (1 day)
+pub const MIN_RECOVERY_DELAY: i64 = 24 * 60 * 60;
+
+/// User registers the guardians who can move their account to a new wallet
+pub(crate) fn set_guardians(
+    ctx: Context<SetGuardians>,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    delay: i64,
+) -> Result<()> {
+    let recovery = &mut ctx.accounts.recovery;
+    recovery.user_account = ctx.accounts.user_account.key();
+    recovery.bump = ctx.bumps.recovery;
+    apply_guardians(recovery, guardians, threshold, delay)
+}
+
+/// User replaces their guardians. Any recovery in progress is dropped.
+pub(crate) fn update_guardians(
+    ctx: Context<UpdateGuardians>,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    delay: i64,
+) -> Result<()> {
+    apply_guardians(&mut ctx.accounts.recovery, guardians, threshold, delay)
+}
+
+/// Guardian approves moving the account to `new_authority`. The first
+/// approval opens the recovery. Each guardian backs one wallet at a time and
+/// can switch until a wallet reaches the threshold, so one guardian opening
+/// a recovery to the wrong wallet can't block the rest. The first wallet to
+/// reach the threshold is locked in and its delay starts.
+pub(crate) fn approve_recovery(ctx: Context<ApproveRecovery>, new_authority: Pubkey) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let guardian = ctx.accounts.guardian.key();
+    require!(
+        new_authority != ctx.accounts.user_account.authority,
+        ErrorCode::InvalidRecoveryTarget
+    );
+    let recovery = &mut ctx.accounts.recovery;
+    let (threshold, delay) = (recovery.threshold, recovery.delay);
+
+    let pending = recovery.pending.get_or_insert_with(|| PendingRecovery {
+        approvals: Vec::new(),
+        started_at: now,
+        new_authority: None,
+        executable_at: None,
+    });
+    if let Some(locked) = pending.new_authority {
+        require_keys_eq!(locked, new_authority, ErrorCode::RecoveryMismatch);
+    }
+    match pending.approvals.iter_mut().find(|a| a.guardian == guardian) {
+        Some(approval) => {
+            require_keys_neq!(approval.new_authority, new_authority, ErrorCode::AlreadyApproved);
+            approval.new_authority = new_authority;
+        }
+        None => pending.approvals.push(RecoveryApproval {
+            guardian,
+            new_authority,
+        }),
+    }
+
+    let approvals = pending.approvals_for(&new_authority);
+    if pending.new_authority.is_none() && approvals >= threshold {
+        pending.new_authority = Some(new_authority);
+        pending.executable_at = Some(now.checked_add(delay).ok_or(ErrorCode::ArithmeticOverflow)?);
+    }
+
+    emit!(RecoveryApproved {
+        user_account: ctx.accounts.user_account.key(),
+        guardian,
+        new_authority,
+        approvals,
+        executable_at: pending.executable_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Current authority cancels a recovery in progress
+pub(crate) fn veto_recovery(ctx: Context<VetoRecovery>) -> Result<()> {
+    let pending = ctx
+        .accounts
+        .recovery
+        .pending
+        .take()
+        .ok_or(ErrorCode::NoPendingRecovery)?;
+
+    emit!(RecoveryVetoed {
+        user_account: ctx.accounts.user_account.key(),
+        new_authority: pending.new_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Move the account to the new authority once the delay has passed. Anyone
+/// may call this.
+pub(crate) fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let recovery = &mut ctx.accounts.recovery;
+    let pending = recovery.pending.as_ref().ok_or(ErrorCode::NoPendingRecovery)?;
+    let new_authority = match (pending.new_authority, pending.executable_at) {
+        (Some(new_authority), Some(at)) if now >= at => new_authority,
+        _ => return err!(ErrorCode::RecoveryNotReady),
+    };
+    recovery.pending = None;
+
+    let user_account = &mut ctx.accounts.user_account;
+    let old_authority = user_account.authority;
+    user_account.authority = new_authority;
+
+    emit!(RecoveryExecuted {
+        user_account: user_account.key(),
+        old_authority,
+        new_authority,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+fn apply_guardians(
+    recovery: &mut Account<GuardianSet>,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    delay: i64,
+) -> Result<()> {
+    require!(
+        !guardians.is_empty() && guardians.len() <= MAX_GUARDIANS,
+        ErrorCode::InvalidSignerSet
+    );
+    for (i, guardian) in guardians.iter().enumerate() {
+        require!(!guardians[..i].contains(guardian), ErrorCode::InvalidSignerSet);
+    }
+    require!(
+        threshold > 0 && threshold as usize <= guardians.len(),
+        ErrorCode::InvalidThreshold
+    );
+    require!(delay >= MIN_RECOVERY_DELAY, ErrorCode::InvalidRecoveryDelay);
+
+    recovery.guardians = guardians;
+    recovery.threshold = threshold;
+    recovery.delay = delay;
+    recovery.pending = None;
+
+    msg!(
+        "Guardians set: {} of {} after {} seconds",
+        threshold,
+        recovery.guardians.len(),
+        delay
+    );
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct SetGuardians<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GuardianSet::INIT_SPACE,
+        seeds = [b"recovery", user_account.key().as_ref()],
+        bump
+    )]
+    pub recovery: Account<'info, GuardianSet>,
+
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"user", user_account.discord_id.as_bytes()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGuardians<'info> {
+    #[account(
+        mut,
+        has_one = user_account @ ErrorCode::AccountMismatch,
+        seeds = [b"recovery", user_account.key().as_ref()],
+        bump = recovery.bump,
+    )]
+    pub recovery: Account<'info, GuardianSet>,
+
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"user", user_account.discord_id.as_bytes()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveRecovery<'info> {
+    #[account(
+        mut,
+        has_one = user_account @ ErrorCode::AccountMismatch,
+        seeds = [b"recovery", user_account.key().as_ref()],
+        bump = recovery.bump,
+        constraint = recovery.guardians.contains(&guardian.key()) @ ErrorCode::NotAGuardian,
+    )]
+    pub recovery: Account<'info, GuardianSet>,
+
+    #[account(
+        seeds = [b"user", user_account.discord_id.as_bytes()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VetoRecovery<'info> {
+    #[account(
+        mut,
+        has_one = user_account @ ErrorCode::AccountMismatch,
+        seeds = [b"recovery", user_account.key().as_ref()],
+        bump = recovery.bump,
+    )]
+    pub recovery: Account<'info, GuardianSet>,
+
+    #[account(
+        has_one = authority @ ErrorCode::Unauthorized,
+        seeds = [b"user", user_account.discord_id.as_bytes()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRecovery<'info> {
+    #[account(
+        mut,
+        has_one = user_account @ ErrorCode::AccountMismatch,
+        seeds = [b"recovery", user_account.key().as_ref()],
+        bump = recovery.bump,
+    )]
+    pub recovery: Account<'info, GuardianSet>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.discord_id.as_bytes()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct GuardianSet {
+    pub user_account: Pubkey,         // 32
+    #[max_len(10)]
+    pub guardians: Vec<Pubkey>,       // 4 + 10 * 32
+    pub threshold: u8,                // 1
+    pub delay: i64,                   // 8
+    pub pending: Option<PendingRecovery>, // 1 + 694
+    pub bump: u8,                     // 1
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PendingRecovery {
+    #[max_len(10)]
+    pub approvals: Vec<RecoveryApproval>, // 4 + 10 * 64 (one per guardian)
+    pub started_at: i64,              // 8
+    pub new_authority: Option<Pubkey>, // 1 + 32 (first wallet to reach the threshold)
+    pub executable_at: Option<i64>,   // 1 + 8 (set with new_authority)
+}
+
+impl PendingRecovery {
+    /// Guardians currently backing `new_authority`
+    pub fn approvals_for(&self, new_authority: &Pubkey) -> u8 {
+        self.approvals
+            .iter()
+            .filter(|a| a.new_authority == *new_authority)
+            .count() as u8
+    }
+}
+
+/// The wallet one guardian currently backs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RecoveryApproval {
+    pub guardian: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+// Events
+
+#[event]
+pub struct RecoveryApproved {
+    pub user_account: Pubkey,
+    pub guardian: Pubkey,
+    pub new_authority: Pubkey,
+    pub approvals: u8,
+    pub executable_at: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryVetoed {
+    pub user_account: Pubkey,
+    pub new_authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryExecuted {
+    pub user_account: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
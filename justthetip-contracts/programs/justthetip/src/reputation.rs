@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, ErrorCode, ScoreUpdated, TrustBadge};
+
+/// Reason code recorded on `ScoreUpdated` when a tip earns reputation
+pub const TIP_ACCRUAL_REASON: u16 = 0;
+/// Length of a daily accrual window
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Open the counter shared by two wallets' tips to each other. Needed before
+/// tips between them can earn reputation.
+pub(crate) fn open_tip_pair(ctx: Context<OpenTipPair>) -> Result<()> {
+    let (wallet_a, wallet_b) = (ctx.accounts.wallet_a.key(), ctx.accounts.wallet_b.key());
+    require!(wallet_a < wallet_b, ErrorCode::InvalidTipPair);
+
+    let tip_pair = &mut ctx.accounts.tip_pair;
+    tip_pair.wallet_a = wallet_a;
+    tip_pair.wallet_b = wallet_b;
+    tip_pair.tips = 0;
+    tip_pair.bump = ctx.bumps.tip_pair;
+    Ok(())
+}
+
+/// Credit reputation for a tip to whichever sides brought a badge. Every tip
+/// between the same two wallets, in either direction, is worth half the one
+/// before, so a pair tipping back and forth earns at most twice the base
+/// amount each, ever. Each badge also has a daily cap. Without a `tip_pair`
+/// nothing accrues, so a badge can be passed just for a trust score check.
+pub(crate) fn accrue_tip_reputation(
+    config: &Config,
+    tip_pair: Option<&mut Account<TipPair>>,
+    sender: Pubkey,
+    recipient: Pubkey,
+    badges: [Option<&mut Account<TrustBadge>>; 2],
+    now: i64,
+) -> Result<()> {
+    let Some(tip_pair) = tip_pair else {
+        return Ok(());
+    };
+    if config.tip_reputation == 0 || badges.iter().all(Option::is_none) {
+        return Ok(());
+    }
+    require!(tip_pair.is_for(sender, recipient), ErrorCode::AccountMismatch);
+
+    let points = config.tip_reputation.checked_shr(tip_pair.tips).unwrap_or(0);
+    tip_pair.tips = tip_pair.tips.saturating_add(1);
+
+    for badge in badges.into_iter().flatten() {
+        badge.settle(config, now);
+        let day = now / SECONDS_PER_DAY;
+        if badge.accrual_day != day {
+            badge.accrual_day = day;
+            badge.accrued_today = 0;
+        }
+        let credited = points.min(
+            config
+                .tip_reputation_daily_cap
+                .saturating_sub(badge.accrued_today),
+        );
+        if credited == 0 {
+            continue;
+        }
+        badge.accrued_today += credited;
+
+        let previous_score = badge.reputation_score;
+        badge.credit_tips(config, credited, now);
+
+        emit!(ScoreUpdated {
+            owner: badge.owner,
+            mint: badge.mint,
+            attester: None,
+            previous_score,
+            reputation_score: badge.reputation_score,
+            reason: TIP_ACCRUAL_REASON,
+        });
+        badge.update_tier(config);
+    }
+
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct OpenTipPair<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TipPair::INIT_SPACE,
+        seeds = [b"tip-pair", wallet_a.key().as_ref(), wallet_b.key().as_ref()],
+        bump
+    )]
+    pub tip_pair: Account<'info, TipPair>,
+
+    /// CHECK: Lower of the two wallet keys
+    pub wallet_a: UncheckedAccount<'info>,
+
+    /// CHECK: Higher of the two wallet keys
+    pub wallet_b: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct TipPair {
+    pub wallet_a: Pubkey,             // 32 (lower key)
+    pub wallet_b: Pubkey,             // 32 (higher key)
+    pub tips: u32,                    // 4
+    pub bump: u8,                     // 1
+}
+
+impl TipPair {
+    /// Whether this counter covers tips between `sender` and `recipient`
+    pub fn is_for(&self, sender: Pubkey, recipient: Pubkey) -> bool {
+        (self.wallet_a, self.wallet_b) == (sender.min(recipient), sender.max(recipient))
+    }
+}
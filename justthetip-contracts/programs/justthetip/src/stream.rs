@@ -0,0 +1,508 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, trust_badge, Config, ErrorCode, TrustBadge, UserAccount};
+
+/// Escrow `amount` that vests linearly to the recipient between
+/// `start_time` and `end_time`. The stream can't start in the past.
+pub(crate) fn start_stream(
+    ctx: Context<StartStream>,
+    amount: u64,
+    start_time: i64,
+    end_time: i64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now <= start_time && start_time < end_time,
+        ErrorCode::InvalidStreamWindow
+    );
+
+    // Large streams need a trusted sender, like large tips
+    let mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+    trust_badge::require_trust_score(
+        ctx.accounts.sender_badge.as_deref_mut(),
+        ctx.accounts.sender_badge_token_account.as_deref(),
+        &ctx.accounts.config,
+        ctx.accounts.config.tip_min_score(mint, amount),
+        now,
+    )?;
+
+    let stream = &mut ctx.accounts.stream;
+    stream.sender = ctx.accounts.sender.key();
+    stream.recipient = ctx.accounts.recipient_account.authority;
+    stream.amount = amount;
+    stream.withdrawn = 0;
+    stream.start_time = start_time;
+    stream.end_time = end_time;
+    stream.bump = ctx.bumps.stream;
+
+    match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.sender_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(sender_token_account), Some(token_program)) => {
+            stream.mint = Some(mint.key());
+
+            let cpi_accounts = Transfer {
+                from: sender_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)?;
+        }
+        (None, None, None, _) => {
+            stream.mint = None;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.stream.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    // The stream counts as one tip; amounts are recorded as they vest out
+    let sender_account = &mut ctx.accounts.sender_account;
+    sender_account.tip_count = sender_account.tip_count
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    let stream = &ctx.accounts.stream;
+    emit!(StreamStarted {
+        stream: stream.key(),
+        sender: stream.sender,
+        recipient: stream.recipient,
+        mint: stream.mint,
+        amount,
+        start_time,
+        end_time,
+    });
+
+    Ok(())
+}
+
+/// Pay the recipient everything that has vested so far
+pub(crate) fn withdraw_stream(ctx: Context<WithdrawStream>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let available = ctx
+        .accounts
+        .stream
+        .vested_amount(now)?
+        .checked_sub(ctx.accounts.stream.withdrawn)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    require!(available > 0, ErrorCode::NothingToWithdraw);
+
+    pay_out(
+        &ctx.accounts.stream,
+        &ctx.accounts.recipient.to_account_info(),
+        ctx.accounts.vault.as_ref(),
+        ctx.accounts.recipient_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+        available,
+    )?;
+    record_payout(
+        &mut ctx.accounts.sender_account,
+        &mut ctx.accounts.recipient_account,
+        available,
+    )?;
+
+    let stream = &mut ctx.accounts.stream;
+    stream.withdrawn = stream.withdrawn
+        .checked_add(available)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(StreamWithdrawn {
+        stream: stream.key(),
+        recipient: stream.recipient,
+        amount: available,
+        withdrawn: stream.withdrawn,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Stop a stream: the recipient gets what has vested and the sender gets the
+/// unvested remainder back.
+pub(crate) fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stream = &ctx.accounts.stream;
+    let vested = stream.vested_amount(now)?;
+    let owed = vested
+        .checked_sub(stream.withdrawn)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    let refund = stream.amount
+        .checked_sub(vested)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    if owed > 0 {
+        pay_out(
+            stream,
+            &ctx.accounts.recipient.to_account_info(),
+            ctx.accounts.vault.as_ref(),
+            ctx.accounts.recipient_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            owed,
+        )?;
+        record_payout(
+            &mut ctx.accounts.sender_account,
+            &mut ctx.accounts.recipient_account,
+            owed,
+        )?;
+    }
+
+    // SOL refunds leave with the account rent when the stream is closed
+    if let (Some(vault), Some(sender_token_account), Some(token_program)) = (
+        &ctx.accounts.vault,
+        &ctx.accounts.sender_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        let stream = &ctx.accounts.stream;
+        let seeds = &[
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            &[stream.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        if refund > 0 {
+            escrow::withdraw_tokens(
+                vault,
+                sender_token_account,
+                stream.to_account_info(),
+                signer,
+                token_program,
+                refund,
+            )?;
+        }
+
+        escrow::close_vault(
+            vault,
+            ctx.accounts.sender.to_account_info(),
+            stream.to_account_info(),
+            signer,
+            token_program,
+        )?;
+    } else {
+        require!(
+            ctx.accounts.stream.mint.is_none(),
+            ErrorCode::MissingTokenAccounts
+        );
+    }
+
+    emit!(StreamCancelled {
+        stream: ctx.accounts.stream.key(),
+        sender: ctx.accounts.stream.sender,
+        recipient: ctx.accounts.stream.recipient,
+        paid_to_recipient: owed,
+        refunded: refund,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Move `amount` out of the stream escrow to the recipient
+fn pay_out<'info>(
+    stream: &Account<'info, TipStream>,
+    recipient: &AccountInfo<'info>,
+    vault: Option<&Account<'info, TokenAccount>>,
+    recipient_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+) -> Result<()> {
+    match stream.mint {
+        Some(mint) => {
+            let (Some(vault), Some(recipient_token_account), Some(token_program)) =
+                (vault, recipient_token_account, token_program)
+            else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(recipient_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(
+                recipient_token_account.owner,
+                stream.recipient,
+                ErrorCode::AccountMismatch
+            );
+
+            let seeds = &[
+                b"stream",
+                stream.sender.as_ref(),
+                stream.recipient.as_ref(),
+                &[stream.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                vault,
+                recipient_token_account,
+                stream.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )
+        }
+        None => {
+            escrow::withdraw_lamports(&stream.to_account_info(), recipient, amount)
+        }
+    }
+}
+
+fn record_payout(
+    sender_account: &mut UserAccount,
+    recipient_account: &mut UserAccount,
+    amount: u64,
+) -> Result<()> {
+    sender_account.total_sent = sender_account.total_sent
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    recipient_account.total_received = recipient_account.total_received
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct StartStream<'info> {
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + TipStream::INIT_SPACE,
+        seeds = [b"stream", sender.key().as_ref(), recipient_account.authority.as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, TipStream>,
+
+    #[account(
+        mut,
+        seeds = [b"user", sender_account.discord_id.as_bytes()],
+        bump = sender_account.bump,
+        constraint = sender_account.authority == sender.key() @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_account: Account<'info, UserAccount>,
+
+    #[account(
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Sender's trust badge, required for large streams
+    #[account(
+        mut,
+        seeds = [b"trust-badge", sender.key().as_ref()],
+        bump = sender_badge.bump,
+    )]
+    pub sender_badge: Option<Box<Account<'info, TrustBadge>>>,
+
+    /// Sender's badge token account, required with `sender_badge` for large streams
+    pub sender_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Only for SPL streams
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL streams
+    #[account(
+        init,
+        payer = sender,
+        seeds = [b"stream-vault", stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = stream,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL streams
+    #[account(mut)]
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStream<'info> {
+    #[account(
+        mut,
+        has_one = recipient @ ErrorCode::Unauthorized,
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref()],
+        bump = stream.bump,
+    )]
+    pub stream: Account<'info, TipStream>,
+
+    #[account(
+        mut,
+        seeds = [b"user", sender_account.discord_id.as_bytes()],
+        bump = sender_account.bump,
+        constraint = sender_account.authority == stream.sender @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_account: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+        constraint = recipient_account.authority == stream.recipient @ ErrorCode::AccountMismatch,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// Only for SPL streams
+    #[account(
+        mut,
+        seeds = [b"stream-vault", stream.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL streams
+    #[account(mut)]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender @ ErrorCode::Unauthorized,
+        has_one = recipient @ ErrorCode::AccountMismatch,
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref()],
+        bump = stream.bump,
+    )]
+    pub stream: Account<'info, TipStream>,
+
+    #[account(
+        mut,
+        seeds = [b"user", sender_account.discord_id.as_bytes()],
+        bump = sender_account.bump,
+        constraint = sender_account.authority == stream.sender @ ErrorCode::AccountMismatch,
+    )]
+    pub sender_account: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user", recipient_account.discord_id.as_bytes()],
+        bump = recipient_account.bump,
+        constraint = recipient_account.authority == stream.recipient @ ErrorCode::AccountMismatch,
+    )]
+    pub recipient_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// CHECK: Only receives lamports; must match the stream recipient
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// Only for SPL streams
+    #[account(
+        mut,
+        seeds = [b"stream-vault", stream.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL streams
+    #[account(mut)]
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL streams
+    #[account(mut)]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct TipStream {
+    pub sender: Pubkey,               // 32
+    pub recipient: Pubkey,            // 32
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub amount: u64,                  // 8
+    pub withdrawn: u64,               // 8
+    pub start_time: i64,              // 8
+    pub end_time: i64,                // 8
+    pub bump: u8,                     // 1
+}
+
+impl TipStream {
+    /// Amount vested to the recipient at `now`, linear between start and end
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        if now <= self.start_time {
+            return Ok(0);
+        }
+        if now >= self.end_time {
+            return Ok(self.amount);
+        }
+        let elapsed = now
+            .checked_sub(self.start_time)
+            .ok_or(ErrorCode::ArithmeticOverflow)? as u128;
+        let duration = self
+            .end_time
+            .checked_sub(self.start_time)
+            .ok_or(ErrorCode::ArithmeticOverflow)? as u128;
+        let vested = (self.amount as u128)
+            .checked_mul(elapsed)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / duration;
+        Ok(vested as u64)
+    }
+}
+
+// Events
+
+#[event]
+pub struct StreamStarted {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct StreamWithdrawn {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub withdrawn: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamCancelled {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub paid_to_recipient: u64,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
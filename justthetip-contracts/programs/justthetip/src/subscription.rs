@@ -0,0 +1,359 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{escrow, ErrorCode, TipEvent, TipStatus, TokenType, UserAccount};
+
+/// Create a recurring tip. Every cycle is prefunded up front: SOL into the
+/// subscription PDA, SPL tokens into a vault owned by it.
+pub(crate) fn create_subscription(
+    ctx: Context<CreateSubscription>,
+    amount: u64,
+    interval: i64,
+    cycles: u32,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(interval > 0, ErrorCode::InvalidInterval);
+    require!(cycles > 0, ErrorCode::InvalidAmount);
+
+    let total = amount
+        .checked_mul(cycles as u64)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    let subscription = &mut ctx.accounts.subscription;
+    subscription.payer = ctx.accounts.payer.key();
+    subscription.payee = ctx.accounts.payee_account.authority;
+    subscription.amount = amount;
+    subscription.interval = interval;
+    subscription.next_due = Clock::get()?.unix_timestamp;
+    subscription.remaining_cycles = cycles;
+    subscription.bump = ctx.bumps.subscription;
+
+    match (
+        &ctx.accounts.mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.payer_token_account,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(mint), Some(vault), Some(payer_token_account), Some(token_program)) => {
+            subscription.mint = Some(mint.key());
+
+            // Prefund the vault with every cycle up front
+            let cpi_accounts = Transfer {
+                from: payer_token_account.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, total)?;
+        }
+        (None, None, None, _) => {
+            subscription.mint = None;
+
+            // Prefund the escrow with every cycle up front
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.subscription.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, total)?;
+        }
+        _ => return err!(ErrorCode::MissingTokenAccounts),
+    }
+
+    msg!(
+        "Subscription created: {} every {}s for {} cycles from {} to {}",
+        amount,
+        interval,
+        cycles,
+        ctx.accounts.payer.key(),
+        ctx.accounts.subscription.payee
+    );
+
+    Ok(())
+}
+
+/// Pay out one due cycle of a subscription. Anyone may crank this.
+pub(crate) fn execute_subscription(ctx: Context<ExecuteSubscription>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let subscription = &ctx.accounts.subscription;
+
+    require!(subscription.remaining_cycles > 0, ErrorCode::SubscriptionComplete);
+    require!(now >= subscription.next_due, ErrorCode::SubscriptionNotDue);
+
+    let amount = subscription.amount;
+    let token_type = match subscription.mint {
+        Some(mint) => {
+            let (Some(vault), Some(payee_token_account), Some(token_program)) = (
+                &ctx.accounts.vault,
+                &ctx.accounts.payee_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_keys_eq!(payee_token_account.mint, mint, ErrorCode::MintMismatch);
+            require_keys_eq!(
+                payee_token_account.owner,
+                subscription.payee,
+                ErrorCode::AccountMismatch
+            );
+
+            let seeds = &[
+                b"subscription",
+                subscription.payer.as_ref(),
+                subscription.payee.as_ref(),
+                &[subscription.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            escrow::withdraw_tokens(
+                vault,
+                payee_token_account,
+                subscription.to_account_info(),
+                signer,
+                token_program,
+                amount,
+            )?;
+            TokenType::SplToken
+        }
+        None => {
+            escrow::withdraw_lamports(
+                &subscription.to_account_info(),
+                &ctx.accounts.payee,
+                amount,
+            )?;
+            TokenType::Sol
+        }
+    };
+
+    let subscription = &mut ctx.accounts.subscription;
+    subscription.remaining_cycles -= 1;
+    subscription.next_due = subscription
+        .next_due
+        .checked_add(subscription.interval)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    // Update payer stats
+    let payer_account = &mut ctx.accounts.payer_account;
+    payer_account.total_sent = payer_account.total_sent
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    payer_account.tip_count = payer_account.tip_count
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    // Update payee stats
+    let payee_account = &mut ctx.accounts.payee_account;
+    payee_account.total_received = payee_account.total_received
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    emit!(TipEvent {
+        sender: ctx.accounts.subscription.payer,
+        recipient: ctx.accounts.subscription.payee,
+        amount,
+        token_type,
+        status: TipStatus::Sent,
+        timestamp: now,
+    });
+
+    msg!(
+        "Subscription paid: {} to {}, {} cycles left",
+        amount,
+        ctx.accounts.subscription.payee,
+        ctx.accounts.subscription.remaining_cycles
+    );
+
+    Ok(())
+}
+
+/// Cancel a subscription, refunding any prefunded escrow to the payer. SOL
+/// refunds leave with the account rent; SPL vaults are emptied and closed.
+pub(crate) fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+    if let Some(mint) = ctx.accounts.subscription.mint {
+        let (Some(vault), Some(payer_token_account), Some(token_program)) = (
+            &ctx.accounts.vault,
+            &ctx.accounts.payer_token_account,
+            &ctx.accounts.token_program,
+        ) else {
+            return err!(ErrorCode::MissingTokenAccounts);
+        };
+        require_keys_eq!(payer_token_account.mint, mint, ErrorCode::MintMismatch);
+
+        let subscription = &ctx.accounts.subscription;
+        let seeds = &[
+            b"subscription",
+            subscription.payer.as_ref(),
+            subscription.payee.as_ref(),
+            &[subscription.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        if vault.amount > 0 {
+            escrow::withdraw_tokens(
+                vault,
+                payer_token_account,
+                subscription.to_account_info(),
+                signer,
+                token_program,
+                vault.amount,
+            )?;
+        }
+
+        escrow::close_vault(
+            vault,
+            ctx.accounts.payer.to_account_info(),
+            subscription.to_account_info(),
+            signer,
+            token_program,
+        )?;
+    }
+
+    msg!(
+        "Subscription cancelled with {} cycles left",
+        ctx.accounts.subscription.remaining_cycles
+    );
+
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct CreateSubscription<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", payer.key().as_ref(), payee_account.authority.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        seeds = [b"user", payer_account.discord_id.as_bytes()],
+        bump = payer_account.bump,
+        constraint = payer_account.authority == payer.key() @ ErrorCode::AccountMismatch,
+    )]
+    pub payer_account: Account<'info, UserAccount>,
+
+    #[account(
+        seeds = [b"user", payee_account.discord_id.as_bytes()],
+        bump = payee_account.bump,
+    )]
+    pub payee_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Only for SPL subscriptions
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Only for SPL subscriptions
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"subscription-vault", subscription.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = subscription,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL subscriptions
+    #[account(mut)]
+    pub payer_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.payer.as_ref(), subscription.payee.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        seeds = [b"user", payer_account.discord_id.as_bytes()],
+        bump = payer_account.bump,
+        constraint = payer_account.authority == subscription.payer @ ErrorCode::AccountMismatch,
+    )]
+    pub payer_account: Account<'info, UserAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"user", payee_account.discord_id.as_bytes()],
+        bump = payee_account.bump,
+        constraint = payee_account.authority == subscription.payee @ ErrorCode::AccountMismatch,
+    )]
+    pub payee_account: Account<'info, UserAccount>,
+
+    /// CHECK: Only receives lamports; must match the subscription payee
+    #[account(mut, address = subscription.payee @ ErrorCode::AccountMismatch)]
+    pub payee: AccountInfo<'info>,
+
+    /// Only for SPL subscriptions
+    #[account(
+        mut,
+        seeds = [b"subscription-vault", subscription.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL subscriptions
+    #[account(mut)]
+    pub payee_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer @ ErrorCode::Unauthorized,
+        seeds = [b"subscription", subscription.payer.as_ref(), subscription.payee.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Only for SPL subscriptions
+    #[account(
+        mut,
+        seeds = [b"subscription-vault", subscription.key().as_ref()],
+        bump,
+    )]
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// Only for SPL subscriptions
+    #[account(mut)]
+    pub payer_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    pub payer: Pubkey,                // 32
+    pub payee: Pubkey,                // 32
+    pub mint: Option<Pubkey>,         // 1 + 32 (None for SOL)
+    pub amount: u64,                  // 8
+    pub interval: i64,                // 8
+    pub next_due: i64,                // 8
+    pub remaining_cycles: u32,        // 4
+    pub bump: u8,                     // 1
+}
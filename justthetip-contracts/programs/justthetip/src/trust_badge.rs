@@ -1,164 +1,823 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+use anchor_spl::token::{
+    self, Approve, Burn, FreezeAccount, Mint, MintTo, ThawAccount, Token, TokenAccount,
+};
 
-#[program]
-pub mod trust_badge {
-    use super::*;
+use crate::{AttesterRegistry, Config, ErrorCode};
 
-    pub fn mint_badge(ctx: Context<MintBadge>, initial_score: u64) -> Result<()> {
-        require!(initial_score <= 10_000, TrustBadgeError::ScoreOutOfRange);
+/// Mint a trust badge to `recipient`. The badge mint is a fresh mint owned by
+/// the program's badge authority, so badges can only be minted through here.
+/// The recipient delegates the badge to that authority, so it can be burned
+/// on revocation, and their token account is frozen straight away, so the
+/// badge can never leave their wallet. Only registered attesters can mint,
+/// and `initial_score` becomes the minting attester's contribution.
+pub(crate) fn mint_badge(ctx: Context<MintBadge>, initial_score: u64) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let registry = &ctx.accounts.registry;
+    let authority = ctx.accounts.authority.key();
+    require!(
+        registry.weight_of(&authority).is_some(),
+        ErrorCode::NotAnAttester
+    );
+    require!(
+        (config.min_score..=config.max_score).contains(&initial_score),
+        ErrorCode::ScoreOutOfRange
+    );
 
-        let badge = &mut ctx.accounts.badge;
-        badge.owner = ctx.accounts.recipient.key();
-        badge.mint = ctx.accounts.badge_mint.key();
-        badge.reputation_score = initial_score;
-        badge.bump = *ctx.bumps.get("badge").ok_or(TrustBadgeError::MissingBump)?;
-        badge.authority = ctx.accounts.authority.key();
+    // A revoked owner has to wait out the cooldown
+    let revocation = &ctx.accounts.revocation;
+    if !revocation.data_is_empty() {
+        let revocation = BadgeRevocation::try_deserialize(&mut &revocation.data.borrow()[..])?;
+        require!(
+            Clock::get()?.unix_timestamp >= revocation.reinstate_at,
+            ErrorCode::BadgeRevoked
+        );
+    }
 
-        let cpi_accounts = MintTo {
-            mint: ctx.accounts.badge_mint.to_account_info(),
-            to: ctx.accounts.recipient_token_account.to_account_info(),
-            authority: ctx.accounts.mint_authority.to_account_info(),
-        };
+    let badge = &mut ctx.accounts.badge;
+    badge.authority = authority;
+    badge.owner = ctx.accounts.recipient.key();
+    badge.mint = ctx.accounts.badge_mint.key();
+    badge.contributions = vec![Contribution {
+        attester: authority,
+        score: initial_score,
+    }];
+    badge.tip_score = 0;
+    let now = Clock::get()?.unix_timestamp;
+    badge.attested_at = now;
+    badge.tip_updated_at = now;
+    badge.recompute(config, registry, now);
+    badge.tier = config.tier_for(badge.reputation_score);
+    badge.accrual_day = 0;
+    badge.accrued_today = 0;
+    badge.bump = ctx.bumps.badge;
 
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::mint_to(cpi_ctx, 1)?;
+    let seeds = &[b"badge-authority".as_ref(), &[ctx.bumps.mint_authority]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.badge_mint.to_account_info(),
+        to: ctx.accounts.recipient_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::mint_to(cpi_ctx, 1)?;
 
-        emit!(BadgeMinted {
-            owner: badge.owner,
-            mint: badge.mint,
-            reputation_score: badge.reputation_score,
-        });
+    let cpi_accounts = Approve {
+        to: ctx.accounts.recipient_token_account.to_account_info(),
+        delegate: ctx.accounts.mint_authority.to_account_info(),
+        authority: ctx.accounts.recipient.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::approve(cpi_ctx, 1)?;
 
-        Ok(())
-    }
+    let cpi_accounts = FreezeAccount {
+        account: ctx.accounts.recipient_token_account.to_account_info(),
+        mint: ctx.accounts.badge_mint.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::freeze_account(cpi_ctx)?;
 
-    pub fn update_score(ctx: Context<UpdateScore>, delta: i64) -> Result<()> {
-        let badge = &mut ctx.accounts.badge;
-        require_keys_eq!(badge.authority, ctx.accounts.authority.key(), TrustBadgeError::Unauthorized);
-
-        let updated_score = if delta.is_negative() {
-            badge
-                .reputation_score
-                .checked_sub(delta.unsigned_abs())
-                .ok_or(TrustBadgeError::ScoreOutOfRange)?
-        } else {
-            badge
-                .reputation_score
-                .checked_add(delta as u64)
-                .ok_or(TrustBadgeError::ScoreOutOfRange)?
-        };
-
-        badge.reputation_score = updated_score;
-        emit!(ScoreUpdated {
-            owner: badge.owner,
-            mint: badge.mint,
-            reputation_score: badge.reputation_score,
-        });
-        Ok(())
-    }
+    emit!(BadgeMinted {
+        owner: badge.owner,
+        mint: badge.mint,
+        reputation_score: badge.reputation_score,
+        tier: badge.tier,
+    });
 
-    pub fn get_score(ctx: Context<GetScore>) -> Result<()> {
-        emit!(ScoreQueried {
-            owner: ctx.accounts.badge.owner,
-            mint: ctx.accounts.badge.mint,
-            reputation_score: ctx.accounts.badge.reputation_score,
-        });
-        Ok(())
+    Ok(())
+}
+
+/// A registered attester moves its own contribution to a badge by `delta`,
+/// keeping it inside the config's score range, and the badge's aggregate
+/// score is recomputed. `reason` is a moderation code passed through to
+/// `ScoreUpdated` for off-chain tooling. Crossing a tier threshold records
+/// the new tier and emits `TierChanged`.
+pub(crate) fn update_score(
+    ctx: Context<UpdateScore>,
+    delta: i64,
+    mode: ScoreMode,
+    reason: u16,
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let registry = &ctx.accounts.registry;
+    let attester = ctx.accounts.attester.key();
+    require!(
+        registry.weight_of(&attester).is_some(),
+        ErrorCode::NotAnAttester
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let badge = &mut ctx.accounts.badge;
+    badge.settle(config, now);
+    let previous_score = badge.reputation_score;
+    badge.rebase_contributions(config, now);
+
+    // Contributions from removed attesters no longer count; drop them to
+    // make room
+    badge
+        .contributions
+        .retain(|c| registry.weight_of(&c.attester).is_some());
+    let index = match badge.contributions.iter().position(|c| c.attester == attester) {
+        Some(index) => index,
+        None => {
+            badge.contributions.push(Contribution {
+                attester,
+                score: config.neutral_score,
+            });
+            badge.contributions.len() - 1
+        }
+    };
+    let contribution = &mut badge.contributions[index];
+    contribution.score = apply_delta(config, contribution.score, delta, mode)?;
+    badge.recompute(config, registry, now);
+
+    emit!(ScoreUpdated {
+        owner: badge.owner,
+        mint: badge.mint,
+        attester: Some(attester),
+        previous_score,
+        reputation_score: badge.reputation_score,
+        reason,
+    });
+    badge.update_tier(config);
+    Ok(())
+}
+
+/// Move `score` by `delta` within `[min_score, max_score]`. `Saturate` stops
+/// at the nearest bound; `Reject` fails if the result would leave the range.
+pub fn apply_delta(config: &Config, score: u64, delta: i64, mode: ScoreMode) -> Result<u64> {
+    let target = score as i128 + delta as i128;
+    let (min, max) = (config.min_score as i128, config.max_score as i128);
+    match mode {
+        ScoreMode::Saturate => Ok(target.clamp(min, max) as u64),
+        ScoreMode::Reject => {
+            require!((min..=max).contains(&target), ErrorCode::ScoreOutOfRange);
+            Ok(target as u64)
+        }
     }
 }
 
+/// A registered attester or the config admin revokes a badge: the token is
+/// thawed and burned, the badge closed, and the owner barred from a new badge
+/// for `badge_cooldown` seconds.
+pub(crate) fn revoke_badge(ctx: Context<RevokeBadge>, reason: u16) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(
+        ctx.accounts.registry.weight_of(&authority).is_some()
+            || ctx.accounts.config.admin == authority,
+        ErrorCode::Unauthorized
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let seeds = &[b"badge-authority".as_ref(), &[ctx.bumps.mint_authority]];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = ThawAccount {
+        account: ctx.accounts.owner_token_account.to_account_info(),
+        mint: ctx.accounts.badge_mint.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::thaw_account(cpi_ctx)?;
+
+    // The authority PDA burns as the delegate set up at mint time
+    let cpi_accounts = Burn {
+        mint: ctx.accounts.badge_mint.to_account_info(),
+        from: ctx.accounts.owner_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::burn(cpi_ctx, 1)?;
+
+    let badge = &ctx.accounts.badge;
+    let reinstate_at = now
+        .checked_add(ctx.accounts.config.badge_cooldown)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    let revocation = &mut ctx.accounts.revocation;
+    revocation.owner = badge.owner;
+    revocation.revoked_by = authority;
+    revocation.reason = reason;
+    revocation.revoked_at = now;
+    revocation.reinstate_at = reinstate_at;
+    revocation.count = revocation.count.saturating_add(1);
+    revocation.bump = ctx.bumps.revocation;
+
+    emit!(BadgeRevoked {
+        owner: badge.owner,
+        mint: badge.mint,
+        revoked_by: revocation.revoked_by,
+        reason,
+        reinstate_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Return a badge's current score, decayed to now, as long as the owner
+/// still holds it. The score comes back as return data, so other programs can
+/// CPI into this and clients can simulate it; nothing is written.
+pub(crate) fn get_score(ctx: Context<GetScore>) -> Result<BadgeScore> {
+    let badge = &ctx.accounts.badge;
+    badge.check_holder(&ctx.accounts.badge_token_account)?;
+    Ok(badge.score_at(&ctx.accounts.config, Clock::get()?.unix_timestamp))
+}
+
+/// Address of `owner`'s TrustBadge PDA
+pub fn badge_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"trust-badge", owner.as_ref()], &crate::ID).0
+}
+
+// Context Structs
+
 #[derive(Accounts)]
 pub struct MintBadge<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub mint_authority: Signer<'info>,
     #[account(
         init,
         payer = authority,
-        space = TrustBadge::LEN,
+        space = 8 + TrustBadge::INIT_SPACE,
         seeds = [b"trust-badge", recipient.key().as_ref()],
-        bump,
+        bump
     )]
-    pub badge: Account<'info, TrustBadge>;
-    #[account(mut)]
-    pub badge_mint: Account<'info, Mint>;
+    pub badge: Account<'info, TrustBadge>,
+
     #[account(
-        init_if_needed,
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+    )]
+    pub badge_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA that holds mint and freeze authority over every badge mint
+    #[account(seeds = [b"badge-authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: The recipient's revocation record, which may not exist yet;
+    /// read in the handler when it does
+    #[account(seeds = [b"badge-revocation", recipient.key().as_ref()], bump)]
+    pub revocation: UncheckedAccount<'info>,
+
+    #[account(
+        init,
         payer = authority,
         associated_token::mint = badge_mint,
         associated_token::authority = recipient,
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>;
-    pub recipient: SystemAccount<'info>;
-    pub token_program: Program<'info, Token>;
-    pub associated_token_program: Program<'info, AssociatedToken>;
-    pub system_program: Program<'info, System>;
-    pub rent: Sysvar<'info, Rent>;
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    /// Signs to delegate the badge to the badge authority
+    pub recipient: Signer<'info>,
+
+    #[account(seeds = [b"attesters"], bump = registry.bump)]
+    pub registry: Account<'info, AttesterRegistry>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateScore<'info> {
+pub struct RevokeBadge<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"trust-badge", badge.owner.as_ref()],
+        bump = badge.bump,
+    )]
+    pub badge: Account<'info, TrustBadge>,
+
+    #[account(mut, address = badge.mint @ ErrorCode::MintMismatch)]
+    pub badge_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = badge_mint,
+        token::authority = badge.owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that holds freeze authority and the owner's delegation
+    #[account(seeds = [b"badge-authority"], bump)]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + BadgeRevocation::INIT_SPACE,
+        seeds = [b"badge-revocation", badge.owner.as_ref()],
+        bump
+    )]
+    pub revocation: Account<'info, BadgeRevocation>,
+
+    #[account(seeds = [b"attesters"], bump = registry.bump)]
+    pub registry: Account<'info, AttesterRegistry>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Registered attester or the config admin
+    #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(mut, has_one = owner)]
-    pub badge: Account<'info, TrustBadge>;
-    /// Owner account used only for constraint verification
-    #[account(address = badge.owner)]
-    pub owner: SystemAccount<'info>;
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateScore<'info> {
+    #[account(
+        mut,
+        seeds = [b"trust-badge", badge.owner.as_ref()],
+        bump = badge.bump,
+    )]
+    pub badge: Account<'info, TrustBadge>,
+
+    #[account(seeds = [b"attesters"], bump = registry.bump)]
+    pub registry: Account<'info, AttesterRegistry>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub attester: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct GetScore<'info> {
-    pub badge: Account<'info, TrustBadge>;
+    #[account(
+        seeds = [b"trust-badge", badge.owner.as_ref()],
+        bump = badge.bump,
+    )]
+    pub badge: Account<'info, TrustBadge>,
+
+    pub badge_token_account: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
 }
 
+// Account Structs
+
 #[account]
+#[derive(InitSpace)]
 pub struct TrustBadge {
-    pub authority: Pubkey,
+    pub authority: Pubkey,            // 32
+    pub owner: Pubkey,                // 32
+    pub mint: Pubkey,                 // 32
+    pub reputation_score: u64,        // 8 (attested_score + tip_score, decayed and clamped)
+    #[max_len(10)]
+    pub contributions: Vec<Contribution>, // 4 + 10 * 40
+    pub attested_score: u64,          // 8 (weighted mean of contributions, as of attested_at)
+    pub tip_score: u64,               // 8 (earned from tips, as of tip_updated_at)
+    pub attested_at: i64,             // 8 (contributions last changed)
+    pub tip_updated_at: i64,          // 8 (tip reputation last credited)
+    pub accrual_day: i64,             // 8 (day of the last tip accrual)
+    pub accrued_today: u64,           // 8 (tip reputation earned that day)
+    pub tier: BadgeTier,              // 1
+    pub bump: u8,                     // 1
+}
+
+impl TrustBadge {
+    /// Recompute the attested score as the weighted mean of contributions
+    /// from registered attesters, then bring the aggregate up to `now`. The
+    /// contributions have to be as of `now`; see `rebase_contributions`.
+    pub fn recompute(&mut self, config: &Config, registry: &AttesterRegistry, now: i64) {
+        let (mut weighted, mut total_weight) = (0u128, 0u128);
+        for contribution in &self.contributions {
+            if let Some(weight) = registry.weight_of(&contribution.attester) {
+                weighted += weight as u128 * contribution.score as u128;
+                total_weight += weight as u128;
+            }
+        }
+        self.attested_score = weighted
+            .checked_div(total_weight)
+            .map_or(config.neutral_score, |score| score as u64);
+        self.apply_decay(config, now);
+    }
+
+    /// Bring the aggregate score up to `now`: the attested score, drifting
+    /// toward the config's neutral score, plus tip reputation, drifting
+    /// toward zero, within the config's score range. Both halve their
+    /// distance every `score_half_life` seconds, counted from when they last
+    /// changed rather than from the last call, so touching a badge more often
+    /// doesn't change how it decays.
+    pub fn apply_decay(&mut self, config: &Config, now: i64) {
+        let attested = decay_toward(
+            self.attested_score,
+            config.neutral_score,
+            now.saturating_sub(self.attested_at),
+            config.score_half_life,
+        );
+        self.reputation_score = attested
+            .saturating_add(self.tip_score_at(config, now))
+            .clamp(config.min_score, config.max_score);
+    }
+
+    /// Tip reputation as of `now`
+    pub fn tip_score_at(&self, config: &Config, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.tip_updated_at);
+        decay_toward(self.tip_score, 0, elapsed, config.score_half_life)
+    }
+
+    /// Fold the decay since `attested_at` into the contributions, so one can
+    /// be changed. `recompute` has to follow.
+    pub fn rebase_contributions(&mut self, config: &Config, now: i64) {
+        let elapsed = now.saturating_sub(self.attested_at);
+        for contribution in &mut self.contributions {
+            contribution.score = decay_toward(
+                contribution.score,
+                config.neutral_score,
+                elapsed,
+                config.score_half_life,
+            );
+        }
+        self.attested_at = now;
+    }
+
+    /// Credit `points` of tip reputation at `now`, on top of what's left of
+    /// the tip reputation so far
+    pub fn credit_tips(&mut self, config: &Config, points: u64, now: i64) {
+        self.tip_score = self.tip_score_at(config, now).saturating_add(points);
+        self.tip_updated_at = now;
+        self.apply_decay(config, now);
+    }
+
+    /// Decode a TrustBadge from raw account data, such as an RPC response,
+    /// checking its discriminator
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        Self::try_deserialize(&mut &data[..])
+    }
+
+    /// The badge's score and tier as of `now`, with decay applied, without
+    /// changing the badge
+    pub fn score_at(&self, config: &Config, now: i64) -> BadgeScore {
+        let mut badge = self.clone();
+        badge.apply_decay(config, now);
+        BadgeScore {
+            owner: badge.owner,
+            mint: badge.mint,
+            reputation_score: badge.reputation_score,
+            tier: config.tier_for(badge.reputation_score),
+        }
+    }
+
+    /// Move the badge to the tier its score has reached, emitting
+    /// `TierChanged` if that's a different one
+    pub fn update_tier(&mut self, config: &Config) {
+        let tier = config.tier_for(self.reputation_score);
+        if tier == self.tier {
+            return;
+        }
+        emit!(TierChanged {
+            owner: self.owner,
+            mint: self.mint,
+            previous_tier: self.tier,
+            tier,
+            reputation_score: self.reputation_score,
+        });
+        self.tier = tier;
+    }
+
+    /// Decay the badge to `now` and move it to the tier its decayed score is
+    /// in. Every write that decays a badge goes through here, so the stored
+    /// tier never lags behind the stored score.
+    pub fn settle(&mut self, config: &Config, now: i64) {
+        self.apply_decay(config, now);
+        self.update_tier(config);
+    }
+
+    /// Check that `token_account` is the owner's frozen badge holding. A badge
+    /// only ever describes its owner.
+    pub fn check_holder(&self, token_account: &TokenAccount) -> Result<()> {
+        require!(
+            token_account.owner == self.owner
+                && token_account.mint == self.mint
+                && token_account.amount == 1
+                && token_account.is_frozen(),
+            ErrorCode::BadgeHolderMismatch
+        );
+        Ok(())
+    }
+}
+
+/// Require `badge` to score at least `min_score`, decayed to `now`, moving it
+/// to the tier the decayed score is in. The badge only counts while its owner
+/// still holds the frozen badge token. With a `min_score` of 0 no badge is
+/// needed.
+pub(crate) fn require_trust_score(
+    badge: Option<&mut Account<TrustBadge>>,
+    badge_token_account: Option<&Account<TokenAccount>>,
+    config: &Config,
+    min_score: u64,
+    now: i64,
+) -> Result<()> {
+    if min_score == 0 {
+        return Ok(());
+    }
+    let badge = badge.ok_or(ErrorCode::TrustScoreTooLow)?;
+    let badge_token_account = badge_token_account.ok_or(ErrorCode::BadgeHolderMismatch)?;
+    badge.check_holder(badge_token_account)?;
+    badge.settle(config, now);
+    require!(
+        badge.reputation_score >= min_score,
+        ErrorCode::TrustScoreTooLow
+    );
+    Ok(())
+}
+
+/// `2^(-1/2^(k+1))` in Q64 fixed point, for each bit `k` of a fraction of a
+/// half-life, most significant first
+const HALF_LIFE_ROOTS: [u64; 32] = [
+    0xb504f333f9de6484,
+    0xd744fccad69d6af4,
+    0xeac0c6e7dd24392f,
+    0xf5257d152486cc2c,
+    0xfa83b2db722a033a,
+    0xfd3e0c0cf486c175,
+    0xfe9e115c7b8f884c,
+    0xff4ecb59511ec8a5,
+    0xffa756521c8daed2,
+    0xffd3a751c0f7e10c,
+    0xffe9d2b2f7db2756,
+    0xfff4e91bff1b8c3e,
+    0xfffa747ea0040664,
+    0xfffd3a3b7814eb54,
+    0xfffe9d1cc60ddab1,
+    0xffff4e8e25879bfa,
+    0xffffa7470363f451,
+    0xffffd3a37dda0313,
+    0xffffe9d1bdf703af,
+    0xfffff4e8debe025e,
+    0xfffffa746f4fa150,
+    0xfffffd3a37a3f8b0,
+    0xfffffe9d1bd1065a,
+    0xffffff4e8de845ae,
+    0xffffffa746f41377,
+    0xffffffd3a37a05e4,
+    0xffffffe9d1bd01fc,
+    0xfffffff4e8de80c0,
+    0xfffffffa746f4051,
+    0xfffffffd3a37a025,
+    0xfffffffe9d1bd011,
+    0xffffffff4e8de808,
+];
+
+/// Move `value` toward `target` by `elapsed` seconds of exponential decay,
+/// halving the distance every `half_life` seconds. The rest of a half-life
+/// is applied as a 32-bit fraction through `HALF_LIFE_ROOTS`, so the result
+/// is the same, up to rounding, however the time is split. A `half_life` of
+/// 0 turns decay off.
+pub fn decay_toward(value: u64, target: u64, elapsed: i64, half_life: i64) -> u64 {
+    if half_life <= 0 || elapsed <= 0 || value == target {
+        return value;
+    }
+    let halvings = (elapsed / half_life) as u32;
+    if halvings >= 64 {
+        return target;
+    }
+    let fraction = (((elapsed % half_life) as u128) << 32) / half_life as u128;
+    let mut factor = 1u128 << 64;
+    for (bit, root) in HALF_LIFE_ROOTS.iter().enumerate() {
+        if fraction & (1 << (31 - bit)) != 0 {
+            factor = (factor * *root as u128) >> 64;
+        }
+    }
+    // Round to nearest
+    let scaled = value.abs_diff(target) as u128 * factor;
+    let distance = (((scaled >> (63 + halvings)) + 1) >> 1) as u64;
+    if value > target {
+        target + distance
+    } else {
+        target - distance
+    }
+}
+
+/// One attester's score for a badge
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct Contribution {
+    pub attester: Pubkey,
+    pub score: u64,
+}
+
+/// Latest revocation of an owner's badge
+#[account]
+#[derive(InitSpace)]
+pub struct BadgeRevocation {
+    pub owner: Pubkey,                // 32
+    pub revoked_by: Pubkey,           // 32
+    pub reason: u16,                  // 2
+    pub revoked_at: i64,              // 8
+    pub reinstate_at: i64,            // 8 (no new badge before this)
+    pub count: u32,                   // 4 (revocations so far)
+    pub bump: u8,                     // 1
+}
+
+/// A badge's decayed score, returned by `get_score`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BadgeScore {
     pub owner: Pubkey,
     pub mint: Pubkey,
     pub reputation_score: u64,
-    pub bump: u8,
+    pub tier: BadgeTier,
 }
 
-impl TrustBadge {
-    pub const LEN: usize = 8 + // discriminator
-        32 + // authority
-        32 + // owner
-        32 + // mint
-        8 +  // reputation_score
-        1;   // bump
+/// Named badge level, set by the config's tier thresholds
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, InitSpace)]
+pub enum BadgeTier {
+    /// Below the first threshold, or no thresholds configured
+    Unranked,
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+    Diamond,
+}
+
+impl BadgeTier {
+    /// Tiers above Unranked, in threshold order
+    pub const RANKED: [BadgeTier; 5] = [
+        BadgeTier::Bronze,
+        BadgeTier::Silver,
+        BadgeTier::Gold,
+        BadgeTier::Platinum,
+        BadgeTier::Diamond,
+    ];
 }
 
+/// How `update_score` handles a delta that would leave the score range
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreMode {
+    /// Clamp to the nearest bound
+    Saturate,
+    /// Fail with `ScoreOutOfRange`
+    Reject,
+}
+
+// Events
+
 #[event]
 pub struct BadgeMinted {
     pub owner: Pubkey,
     pub mint: Pubkey,
     pub reputation_score: u64,
+    pub tier: BadgeTier,
 }
 
 #[event]
 pub struct ScoreUpdated {
     pub owner: Pubkey,
     pub mint: Pubkey,
+    pub attester: Option<Pubkey>,
+    pub previous_score: u64,
     pub reputation_score: u64,
+    pub reason: u16,
 }
 
 #[event]
-pub struct ScoreQueried {
+pub struct TierChanged {
     pub owner: Pubkey,
     pub mint: Pubkey,
+    pub previous_tier: BadgeTier,
+    pub tier: BadgeTier,
     pub reputation_score: u64,
 }
 
-#[error_code]
-pub enum TrustBadgeError {
-    #[msg("Trust badge PDA missing bump seed")]
-    MissingBump,
-    #[msg("Caller is not authorized to update the reputation score")]
-    Unauthorized,
-    #[msg("Reputation score is out of range")]
-    ScoreOutOfRange,
+#[event]
+pub struct BadgeRevoked {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub revoked_by: Pubkey,
+    pub reason: u16,
+    pub reinstate_at: i64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF_LIFE: i64 = 30 * 24 * 60 * 60;
+
+    fn config() -> Config {
+        Config {
+            admin: Pubkey::default(),
+            treasury: Pubkey::default(),
+            fee_bps: 0,
+            referral_share_bps: 0,
+            referral_period: 0,
+            large_airdrop_threshold: 0,
+            paused: false,
+            min_sol_tip: 0,
+            min_token_tips: vec![],
+            rate_limit_tips: 0,
+            rate_limit_slots: 0,
+            min_score: 0,
+            max_score: 1_000,
+            tip_reputation: 10,
+            tip_reputation_daily_cap: 50,
+            badge_cooldown: 0,
+            neutral_score: 500,
+            score_half_life: HALF_LIFE,
+            airdrop_min_score: 0,
+            large_tip_amount: 0,
+            large_tip_min_score: 0,
+            tier_thresholds: vec![600, 700, 800, 900, 950],
+            pending_change: None,
+            bump: 0,
+        }
+    }
+
+    fn badge(attested_score: u64, tip_score: u64) -> TrustBadge {
+        TrustBadge {
+            authority: Pubkey::default(),
+            owner: Pubkey::default(),
+            mint: Pubkey::default(),
+            reputation_score: attested_score + tip_score,
+            contributions: vec![],
+            attested_score,
+            tip_score,
+            attested_at: 0,
+            tip_updated_at: 0,
+            accrual_day: 0,
+            accrued_today: 0,
+            tier: BadgeTier::Unranked,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn decay_halves_the_distance_every_half_life() {
+        assert_eq!(decay_toward(900, 500, HALF_LIFE, HALF_LIFE), 700);
+        assert_eq!(decay_toward(100, 500, 2 * HALF_LIFE, HALF_LIFE), 400);
+        assert_eq!(decay_toward(900, 500, HALF_LIFE / 2, HALF_LIFE), 783);
+        assert_eq!(decay_toward(900, 500, 64 * HALF_LIFE, HALF_LIFE), 500);
+        assert_eq!(decay_toward(900, 500, HALF_LIFE, 0), 900);
+    }
+
+    #[test]
+    fn decay_in_steps_matches_one_step() {
+        let value = 1_000_000_000;
+        let mut stepped = value;
+        for _ in 0..4 {
+            stepped = decay_toward(stepped, 0, HALF_LIFE / 4, HALF_LIFE);
+        }
+        assert!(stepped.abs_diff(decay_toward(value, 0, HALF_LIFE, HALF_LIFE)) <= 2);
+    }
+
+    #[test]
+    fn many_small_touches_decay_like_one_large_one() {
+        let config = config();
+        let mut touched = badge(900, 40);
+        let mut untouched = touched.clone();
+        let mut now = 0;
+        while now < 3 * HALF_LIFE {
+            now += 60;
+            touched.apply_decay(&config, now);
+        }
+        untouched.apply_decay(&config, now);
+        assert_eq!(touched.reputation_score, untouched.reputation_score);
+        assert_eq!(touched.reputation_score, 500 + 50 + 5);
+    }
+
+    #[test]
+    fn tips_do_not_hold_back_attested_decay() {
+        let config = config();
+        let mut tipped = badge(900, 0);
+        let mut idle = tipped.clone();
+        let mut now = 0;
+        while now < HALF_LIFE {
+            now += HALF_LIFE / 8;
+            tipped.credit_tips(&config, 0, now);
+        }
+        idle.apply_decay(&config, now);
+        assert_eq!(tipped.reputation_score, idle.reputation_score);
+        assert_eq!(tipped.reputation_score, 700);
+    }
+
+    #[test]
+    fn settling_moves_a_decayed_badge_down_a_tier() {
+        let config = config();
+        let mut badge = badge(900, 0);
+        badge.tier = config.tier_for(900);
+        assert_eq!(badge.tier, BadgeTier::Platinum);
+
+        badge.settle(&config, HALF_LIFE);
+        assert_eq!(badge.reputation_score, 700);
+        assert_eq!(badge.tier, BadgeTier::Silver);
+        assert_eq!(badge.score_at(&config, HALF_LIFE).tier, badge.tier);
+    }
 }
@@ -0,0 +1,201 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::token::TokenAccount;
+
+use crate::{escrow, trust_badge, Airdrop, AirdropClaimEvent, Config, ErrorCode, TrustBadge};
+
+/// Size of the signed voucher message: airdrop, claimer, amount, expiry
+pub const VOUCHER_MESSAGE_LEN: usize = 32 + 32 + 8 + 8;
+
+// Layout of an Ed25519 program instruction with a single signature
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_PUBKEY_LEN: usize = 32;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// Claim `amount` from a voucher airdrop. The transaction must carry an
+/// Ed25519 program instruction, just before this one, in which the airdrop's
+/// voucher signer signed (airdrop, claimer, amount, expiry).
+pub(crate) fn claim_airdrop_voucher(
+    ctx: Context<ClaimAirdropVoucher>,
+    amount: u64,
+    expiry: i64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let airdrop = &ctx.accounts.airdrop;
+    let voucher_signer = airdrop.voucher_signer.ok_or(ErrorCode::NotAVoucherAirdrop)?;
+    require!(airdrop.is_active, ErrorCode::AirdropInactive);
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(now < expiry, ErrorCode::VoucherExpired);
+    trust_badge::require_trust_score(
+        ctx.accounts.claimer_badge.as_deref_mut(),
+        ctx.accounts.claimer_badge_token_account.as_deref(),
+        &ctx.accounts.config,
+        airdrop.min_claim_score.max(ctx.accounts.config.airdrop_min_score),
+        now,
+    )?;
+
+    let message = voucher_message(&airdrop.key(), &ctx.accounts.claimer.key(), amount, expiry);
+    verify_ed25519_voucher(&ctx.accounts.instructions, &voucher_signer, &message)?;
+
+    require!(
+        airdrop.claimed_count < airdrop.recipients_count,
+        ErrorCode::AirdropFullyClaimed
+    );
+    let claimed_amount = airdrop.claimed_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    require!(
+        claimed_amount <= airdrop.total_amount,
+        ErrorCode::AirdropFullyClaimed
+    );
+
+    escrow::withdraw_lamports(&ctx.accounts.airdrop.to_account_info(), &ctx.accounts.claimer, amount)?;
+
+    let airdrop = &mut ctx.accounts.airdrop;
+    airdrop.claimed_amount = claimed_amount;
+    airdrop.claimed_count += 1;
+    if airdrop.claimed_count >= airdrop.recipients_count || claimed_amount == airdrop.total_amount {
+        airdrop.is_active = false;
+    }
+
+    let claim = &mut ctx.accounts.claim;
+    claim.airdrop = airdrop.key();
+    claim.claimer = ctx.accounts.claimer.key();
+    claim.amount = amount;
+    claim.claimed_at = now;
+    claim.bump = ctx.bumps.claim;
+
+    emit!(AirdropClaimEvent {
+        airdrop: airdrop.key(),
+        claimer: claim.claimer,
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Bytes the voucher signer signs for a claim
+pub fn voucher_message(
+    airdrop: &Pubkey,
+    claimer: &Pubkey,
+    amount: u64,
+    expiry: i64,
+) -> [u8; VOUCHER_MESSAGE_LEN] {
+    let mut message = [0u8; VOUCHER_MESSAGE_LEN];
+    message[..32].copy_from_slice(airdrop.as_ref());
+    message[32..64].copy_from_slice(claimer.as_ref());
+    message[64..72].copy_from_slice(&amount.to_le_bytes());
+    message[72..].copy_from_slice(&expiry.to_le_bytes());
+    message
+}
+
+/// Check that the instruction before this one is an Ed25519 verification of
+/// `message` by `signer`. The Ed25519 program has already checked the
+/// signature itself; here we make sure it covered the right key and bytes.
+fn verify_ed25519_voucher(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, ErrorCode::InvalidVoucher);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidVoucher);
+
+    let data = &ix.data;
+    require!(
+        data.len() >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN && data[0] == 1,
+        ErrorCode::InvalidVoucher
+    );
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let offsets = ED25519_HEADER_LEN;
+    let signature_offset = read_u16(offsets) as usize;
+    let signature_ix = read_u16(offsets + 2);
+    let pubkey_offset = read_u16(offsets + 4) as usize;
+    let pubkey_ix = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    let message_ix = read_u16(offsets + 12);
+
+    // All data must live in the Ed25519 instruction itself
+    require!(
+        signature_ix == u16::MAX && pubkey_ix == u16::MAX && message_ix == u16::MAX,
+        ErrorCode::InvalidVoucher
+    );
+    require!(
+        data.len() >= signature_offset + ED25519_SIGNATURE_LEN
+            && data.len() >= pubkey_offset + ED25519_PUBKEY_LEN
+            && data.len() >= message_offset + message_size,
+        ErrorCode::InvalidVoucher
+    );
+    require!(
+        &data[pubkey_offset..pubkey_offset + ED25519_PUBKEY_LEN] == signer.as_ref()
+            && &data[message_offset..message_offset + message_size] == message,
+        ErrorCode::InvalidVoucher
+    );
+
+    Ok(())
+}
+
+// Context Structs
+
+#[derive(Accounts)]
+pub struct ClaimAirdropVoucher<'info> {
+    #[account(
+        mut,
+        seeds = [b"airdrop", airdrop.creator.as_ref(), &airdrop.airdrop_id.to_le_bytes()],
+        bump = airdrop.bump,
+    )]
+    pub airdrop: Account<'info, Airdrop>,
+
+    /// One claim per wallet per airdrop
+    #[account(
+        init,
+        payer = claimer,
+        space = 8 + AirdropClaim::INIT_SPACE,
+        seeds = [b"airdrop-claim", airdrop.key().as_ref(), claimer.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, AirdropClaim>,
+
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    /// Claimer's trust badge, required when the airdrop has a minimum score
+    #[account(
+        mut,
+        seeds = [b"trust-badge", claimer.key().as_ref()],
+        bump = claimer_badge.bump,
+    )]
+    pub claimer_badge: Option<Box<Account<'info, TrustBadge>>>,
+
+    /// Claimer's badge token account, required with `claimer_badge` when the airdrop has a minimum score
+    pub claimer_badge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Account Structs
+
+#[account]
+#[derive(InitSpace)]
+pub struct AirdropClaim {
+    pub airdrop: Pubkey,              // 32
+    pub claimer: Pubkey,              // 32
+    pub amount: u64,                  // 8
+    pub claimed_at: i64,              // 8
+    pub bump: u8,                     // 1
+}
@@ -16,7 +16,18 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Justthetip } from "../target/types/justthetip";
-import { PublicKey, SystemProgram, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  PublicKey,
+  SystemProgram,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  SYSVAR_SLOT_HASHES_PUBKEY,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Ed25519Program,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { createHash, randomBytes } from "crypto";
 import { assert } from "chai";
 
 describe("justthetip", () => {
@@ -28,6 +39,7 @@ describe("justthetip", () => {
   
   const user1 = Keypair.generate();
   const user2 = Keypair.generate();
+  const moderator = Keypair.generate();
   const discordId1 = "discord_user_123";
   const discordId2 = "discord_user_456";
 
@@ -36,6 +48,133 @@ describe("justthetip", () => {
   let user2AccountPda: PublicKey;
   let user1Bump: number;
   let user2Bump: number;
+  let configPda: PublicKey;
+  const treasury = provider.wallet.publicKey;
+  const FEE_BPS = 100;
+  const MIN_SOL_TIP = 10_000;
+  const RATE_LIMIT_TIPS = 5;
+  const RATE_LIMIT_SLOTS = 1_000;
+  const MAX_SCORE = 10_000;
+  const TIP_REPUTATION = 10;
+  const TIP_REPUTATION_DAILY_CAP = 50;
+  const BADGE_COOLDOWN = 7 * 24 * 60 * 60;
+  const SCORE_HALF_LIFE = 30 * 24 * 60 * 60;
+  const LARGE_TIP = 1 * LAMPORTS_PER_SOL;
+  const LARGE_TIP_MIN_SCORE = 500;
+  const LARGE_AIRDROP = 0.5 * LAMPORTS_PER_SOL;
+  const TIER_THRESHOLDS = [250, 500, 1_000, 2_500, 5_000];
+  const afterFee = (amount: anchor.BN) => amount.toNumber() - (amount.toNumber() * FEE_BPS) / 10_000;
+
+  const [badgeAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("badge-authority")],
+    program.programId
+  );
+  const [attesterRegistry] = PublicKey.findProgramAddressSync(
+    [Buffer.from("attesters")],
+    program.programId
+  );
+  const badgeAccounts = (owner: PublicKey, badgeMint: PublicKey) => ({
+    badge: PublicKey.findProgramAddressSync(
+      [Buffer.from("trust-badge"), owner.toBuffer()],
+      program.programId
+    )[0],
+    revocation: PublicKey.findProgramAddressSync(
+      [Buffer.from("badge-revocation"), owner.toBuffer()],
+      program.programId
+    )[0],
+    tokenAccount: anchor.utils.token.associatedAddress({ mint: badgeMint, owner }),
+  });
+
+  // Mint a trust badge to `recipient`, with the provider wallet as minting attester
+  const mintBadge = async (recipient: Keypair, score: number) => {
+    const badgeMint = Keypair.generate();
+    const { badge, revocation, tokenAccount } = badgeAccounts(recipient.publicKey, badgeMint.publicKey);
+    await program.methods
+      .mintBadge(new anchor.BN(score))
+      .accounts({
+        badge,
+        badgeMint: badgeMint.publicKey,
+        mintAuthority: badgeAuthority,
+        revocation,
+        recipientTokenAccount: tokenAccount,
+        recipient: recipient.publicKey,
+        registry: attesterRegistry,
+        config: configPda,
+        authority: provider.wallet.publicKey,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([recipient, badgeMint])
+      .rpc();
+    return { badge, revocation, badgeMint: badgeMint.publicKey, tokenAccount };
+  };
+
+  // Minimal SPL helpers built from raw token program instructions, with the
+  // provider wallet paying and acting as mint authority
+  const TOKEN_PROGRAM_ID = anchor.utils.token.TOKEN_PROGRAM_ID;
+  const createTokenAccountIx = async (account: PublicKey, space: number) =>
+    SystemProgram.createAccount({
+      fromPubkey: provider.wallet.publicKey,
+      newAccountPubkey: account,
+      lamports: await provider.connection.getMinimumBalanceForRentExemption(space),
+      space,
+      programId: TOKEN_PROGRAM_ID,
+    });
+  const createMint = async (decimals: number) => {
+    const mint = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        await createTokenAccountIx(mint.publicKey, 82),
+        // InitializeMint2 with no freeze authority
+        new TransactionInstruction({
+          programId: TOKEN_PROGRAM_ID,
+          keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+          data: Buffer.concat([
+            Buffer.from([20, decimals]),
+            provider.wallet.publicKey.toBuffer(),
+            Buffer.from([0]),
+          ]),
+        })
+      ),
+      [mint]
+    );
+    return mint.publicKey;
+  };
+  const createTokenAccount = async (mint: PublicKey, owner: PublicKey, amount = 0) => {
+    const account = Keypair.generate();
+    const tx = new Transaction().add(
+      await createTokenAccountIx(account.publicKey, 165),
+      // InitializeAccount3
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: account.publicKey, isSigner: false, isWritable: true },
+          { pubkey: mint, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([18]), owner.toBuffer()]),
+      })
+    );
+    if (amount > 0) {
+      // MintTo
+      tx.add(
+        new TransactionInstruction({
+          programId: TOKEN_PROGRAM_ID,
+          keys: [
+            { pubkey: mint, isSigner: false, isWritable: true },
+            { pubkey: account.publicKey, isSigner: false, isWritable: true },
+            { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false },
+          ],
+          data: Buffer.concat([Buffer.from([7]), new anchor.BN(amount).toArrayLike(Buffer, "le", 8)]),
+        })
+      );
+    }
+    await provider.sendAndConfirm(tx, [account]);
+    return account.publicKey;
+  };
+  const tokenBalance = async (account: PublicKey) =>
+    Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
 
   before(async () => {
     // Airdrop SOL to test users
@@ -56,6 +195,65 @@ describe("justthetip", () => {
       [Buffer.from("user"), Buffer.from(discordId2)],
       program.programId
     );
+
+    // Create the config; only the upgrade authority can
+    [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    await program.methods
+      .initializeConfig({
+        treasury,
+        feeBps: FEE_BPS,
+        referralShareBps: 2_000,
+        referralPeriod: new anchor.BN(90 * 24 * 60 * 60),
+        largeAirdropThreshold: new anchor.BN(LARGE_AIRDROP),
+        paused: false,
+        minSolTip: new anchor.BN(MIN_SOL_TIP),
+        minTokenTips: [],
+        rateLimitTips: RATE_LIMIT_TIPS,
+        rateLimitSlots: new anchor.BN(RATE_LIMIT_SLOTS),
+        minScore: new anchor.BN(0),
+        maxScore: new anchor.BN(MAX_SCORE),
+        tipReputation: new anchor.BN(TIP_REPUTATION),
+        tipReputationDailyCap: new anchor.BN(TIP_REPUTATION_DAILY_CAP),
+        badgeCooldown: new anchor.BN(BADGE_COOLDOWN),
+        neutralScore: new anchor.BN(0),
+        scoreHalfLife: new anchor.BN(SCORE_HALF_LIFE),
+        airdropMinScore: new anchor.BN(0),
+        largeTipAmount: new anchor.BN(LARGE_TIP),
+        largeTipMinScore: new anchor.BN(LARGE_TIP_MIN_SCORE),
+        tierThresholds: TIER_THRESHOLDS.map((t) => new anchor.BN(t)),
+      })
+      .accounts({
+        config: configPda,
+        admin: provider.wallet.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Register the trust badge attesters while the wallet is still admin
+    await program.methods
+      .initializeAttesterRegistry()
+      .accounts({
+        registry: attesterRegistry,
+        config: configPda,
+        admin: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    for (const [attester, weight] of [
+      [provider.wallet.publicKey, 1],
+      [moderator.publicKey, 3],
+    ] as [PublicKey, number][]) {
+      await program.methods
+        .setAttester(attester, weight)
+        .accounts({ registry: attesterRegistry, config: configPda, admin: provider.wallet.publicKey })
+        .rpc();
+    }
   });
 
   it("Initializes user accounts", async () => {
@@ -65,6 +263,7 @@ describe("justthetip", () => {
       .accounts({
         userAccount: user1AccountPda,
         authority: user1.publicKey,
+        referrerAccount: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([user1])
@@ -76,6 +275,7 @@ describe("justthetip", () => {
       .accounts({
         userAccount: user2AccountPda,
         authority: user2.publicKey,
+        referrerAccount: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([user2])
@@ -95,6 +295,24 @@ describe("justthetip", () => {
     assert.equal(user2Account.discordId, discordId2);
   });
 
+  it("Leaves current user accounts alone when migrating", async () => {
+    const before = await provider.connection.getAccountInfo(user1AccountPda);
+    await program.methods
+      .migrateUserAccount(discordId1)
+      .accounts({
+        userAccount: user1AccountPda,
+        payer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const after = await provider.connection.getAccountInfo(user1AccountPda);
+    assert.equal(after.data.length, before.data.length);
+    assert.equal(after.lamports, before.lamports);
+    const account = await program.account.userAccount.fetch(user1AccountPda);
+    assert.ok(account.authority.equals(user1.publicKey));
+  });
+
   it("Sends a SOL tip from user1 to user2", async () => {
     const tipAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
 
@@ -105,6 +323,16 @@ describe("justthetip", () => {
         recipientAccount: user2AccountPda,
         sender: user1.publicKey,
         recipient: user2.publicKey,
+        matchingPool: null,
+        matchRecord: null,
+        config: configPda,
+        treasury,
+        referrerAccount: null,
+        senderBadge: null,
+        senderBadgeTokenAccount: null,
+        recipientBadge: null,
+        tipPair: null,
+        guildTreasury: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([user1])
@@ -117,29 +345,27 @@ describe("justthetip", () => {
 
     // Verify recipient stats updated
     const recipientAccount = await program.account.userAccount.fetch(user2AccountPda);
-    assert.equal(recipientAccount.totalReceived.toNumber(), tipAmount.toNumber());
+    assert.equal(recipientAccount.totalReceived.toNumber(), afterFee(tipAmount));
   });
 
   it("Creates an airdrop", async () => {
     const creator = user1;
-    const totalAmount = new anchor.BN(1 * LAMPORTS_PER_SOL);
+    const totalAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
     const recipientsCount = 10;
 
-    const timestamp = Math.floor(Date.now() / 1000);
+    const airdropId = new anchor.BN(1);
     const [airdropPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("airdrop"),
-        creator.publicKey.toBuffer(),
-        new anchor.BN(timestamp).toArrayLike(Buffer, "le", 8),
-      ],
+      [Buffer.from("airdrop"), creator.publicKey.toBuffer(), airdropId.toArrayLike(Buffer, "le", 8)],
       program.programId
     );
 
     await program.methods
-      .createAirdrop(totalAmount, recipientsCount)
+      .createAirdrop(airdropId, totalAmount, recipientsCount, null, new anchor.BN(0))
       .accounts({
         airdrop: airdropPda,
         creator: creator.publicKey,
+        config: configPda,
+        approval: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([creator])
@@ -152,6 +378,15 @@ describe("justthetip", () => {
     assert.equal(airdrop.recipientsCount, recipientsCount);
     assert.equal(airdrop.claimedCount, 0);
     assert.equal(airdrop.isActive, true);
+
+    // The whole amount is escrowed on creation
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(airdropPda)).data.length
+    );
+    assert.equal(
+      (await provider.connection.getBalance(airdropPda)) - rent,
+      totalAmount.toNumber()
+    );
   });
 
   it("Multiple users can send tips", async () => {
@@ -165,6 +400,16 @@ describe("justthetip", () => {
         recipientAccount: user1AccountPda,
         sender: user2.publicKey,
         recipient: user1.publicKey,
+        matchingPool: null,
+        matchRecord: null,
+        config: configPda,
+        treasury,
+        referrerAccount: null,
+        senderBadge: null,
+        senderBadgeTokenAccount: null,
+        recipientBadge: null,
+        tipPair: null,
+        guildTreasury: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([user2])
@@ -178,4 +423,2030 @@ describe("justthetip", () => {
     const recipient = await program.account.userAccount.fetch(user1AccountPda);
     assert.ok(recipient.totalReceived.toNumber() > 0);
   });
+
+  it("Sends an NFT tip from user2 to user1", async () => {
+    const mint = await createMint(0);
+    const senderTokenAccount = await createTokenAccount(mint, user2.publicKey, 1);
+    const recipientTokenAccount = await createTokenAccount(mint, user1.publicKey);
+
+    const before = await program.account.userAccount.fetch(user2AccountPda);
+
+    const tipNft = (badge: { badge: PublicKey; tokenAccount: PublicKey } | null) =>
+      program.methods
+        .tipNft()
+        .accounts({
+          senderAccount: user2AccountPda,
+          recipientAccount: user1AccountPda,
+          sender: user2.publicKey,
+          mint,
+          senderTokenAccount,
+          recipientTokenAccount,
+          config: configPda,
+          senderBadge: badge?.badge ?? null,
+          senderBadgeTokenAccount: badge?.tokenAccount ?? null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc();
+
+    // NFTs count as large tips, so the sender needs a trusted badge
+    try {
+      await tipNft(null);
+      assert.fail("NFT tips without a badge should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TrustScoreTooLow");
+    }
+
+    await tipNft(await mintBadge(user2, LARGE_TIP_MIN_SCORE));
+
+    assert.equal(await tokenBalance(senderTokenAccount), 0);
+    assert.equal(await tokenBalance(recipientTokenAccount), 1);
+
+    const after = await program.account.userAccount.fetch(user2AccountPda);
+    assert.equal(after.tipCount.toNumber() - before.tipCount.toNumber(), 1);
+    assert.equal(after.totalSent.toNumber() - before.totalSent.toNumber(), 1);
+  });
+
+  it("Rejects NFT tips of fungible mints", async () => {
+    // More than one token minted, and a single token with decimals
+    const mints = [
+      { decimals: 0, supply: 2 },
+      { decimals: 6, supply: 1 },
+    ];
+
+    for (const { decimals, supply } of mints) {
+      const mint = await createMint(decimals);
+      const senderTokenAccount = await createTokenAccount(mint, user2.publicKey, supply);
+      const recipientTokenAccount = await createTokenAccount(mint, user1.publicKey);
+
+      try {
+        await program.methods
+          .tipNft()
+          .accounts({
+            senderAccount: user2AccountPda,
+            recipientAccount: user1AccountPda,
+            sender: user2.publicKey,
+            mint,
+            senderTokenAccount,
+            recipientTokenAccount,
+            config: configPda,
+            senderBadge: null,
+            senderBadgeTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user2])
+          .rpc();
+        assert.fail("Fungible mints should be rejected");
+      } catch (err) {
+        assert.include(err.toString(), "NotAnNft");
+      }
+      assert.equal(await tokenBalance(senderTokenAccount), supply);
+    }
+  });
+
+  it("Creates and cranks a SOL subscription", async () => {
+    const amount = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+    const [subscriptionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("subscription"), user1.publicKey.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createSubscription(amount, new anchor.BN(7 * 24 * 60 * 60), 4)
+      .accounts({
+        subscription: subscriptionPda,
+        payerAccount: user1AccountPda,
+        payeeAccount: user2AccountPda,
+        payer: user1.publicKey,
+        mint: null,
+        vault: null,
+        payerTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const before = await program.account.userAccount.fetch(user2AccountPda);
+
+    // The first cycle is due immediately and anyone can crank it
+    await program.methods
+      .executeSubscription()
+      .accounts({
+        subscription: subscriptionPda,
+        payerAccount: user1AccountPda,
+        payeeAccount: user2AccountPda,
+        payee: user2.publicKey,
+        vault: null,
+        payeeTokenAccount: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    const subscription = await program.account.subscription.fetch(subscriptionPda);
+    assert.equal(subscription.remainingCycles, 3);
+
+    const after = await program.account.userAccount.fetch(user2AccountPda);
+    assert.equal(
+      after.totalReceived.toNumber() - before.totalReceived.toNumber(),
+      amount.toNumber()
+    );
+
+    await program.methods
+      .cancelSubscription()
+      .accounts({
+        subscription: subscriptionPda,
+        payer: user1.publicKey,
+        vault: null,
+        payerTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user1])
+      .rpc();
+
+    assert.isNull(await program.account.subscription.fetchNullable(subscriptionPda));
+  });
+
+  it("Escrows two SPL subscriptions from the same token account", async () => {
+    const payee2 = Keypair.generate();
+    const discordId = "discord_user_payee2";
+    const [payee2AccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), Buffer.from(discordId)],
+      program.programId
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payee2.publicKey, LAMPORTS_PER_SOL)
+    );
+    await program.methods
+      .initializeUser(discordId)
+      .accounts({
+        userAccount: payee2AccountPda,
+        authority: payee2.publicKey,
+        referrerAccount: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([payee2])
+      .rpc();
+
+    const mint = await createMint(6);
+    const payerTokenAccount = await createTokenAccount(mint, user1.publicKey, 1_000_000);
+    const amount = new anchor.BN(100_000);
+    const cycles = 3;
+
+    const payees = [
+      { wallet: user2.publicKey, account: user2AccountPda },
+      { wallet: payee2.publicKey, account: payee2AccountPda },
+    ];
+    const subscriptions = [];
+    for (const payee of payees) {
+      const [subscription] = PublicKey.findProgramAddressSync(
+        [Buffer.from("subscription"), user1.publicKey.toBuffer(), payee.wallet.toBuffer()],
+        program.programId
+      );
+      const [vault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("subscription-vault"), subscription.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .createSubscription(amount, new anchor.BN(7 * 24 * 60 * 60), cycles)
+        .accounts({
+          subscription,
+          payerAccount: user1AccountPda,
+          payeeAccount: payee.account,
+          payer: user1.publicKey,
+          mint,
+          vault,
+          payerTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+      subscriptions.push({ ...payee, subscription, vault });
+    }
+
+    // Each subscription holds its own escrow, so creating the second one
+    // can't take anything away from the first
+    assert.equal(await tokenBalance(payerTokenAccount), 1_000_000 - 2 * cycles * amount.toNumber());
+    for (const { wallet, account, subscription, vault } of subscriptions) {
+      assert.equal(await tokenBalance(vault), cycles * amount.toNumber());
+
+      const payeeTokenAccount = await createTokenAccount(mint, wallet);
+      await program.methods
+        .executeSubscription()
+        .accounts({
+          subscription,
+          payerAccount: user1AccountPda,
+          payeeAccount: account,
+          payee: wallet,
+          vault,
+          payeeTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.equal(await tokenBalance(payeeTokenAccount), amount.toNumber());
+    }
+
+    // Cancelling refunds the unpaid cycles and closes the vault
+    const { subscription, vault } = subscriptions[0];
+    const before = await tokenBalance(payerTokenAccount);
+    await program.methods
+      .cancelSubscription()
+      .accounts({
+        subscription,
+        payer: user1.publicKey,
+        vault,
+        payerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user1])
+      .rpc();
+
+    assert.equal(
+      (await tokenBalance(payerTokenAccount)) - before,
+      (cycles - 1) * amount.toNumber()
+    );
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+    assert.equal(await tokenBalance(subscriptions[1].vault), (cycles - 1) * amount.toNumber());
+  });
+
+  it("Streams a SOL tip and cancels with a refund", async () => {
+    const amount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const chainTime = async () =>
+      await provider.connection.getBlockTime(await provider.connection.getSlot());
+    const [streamPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stream"), user2.publicKey.toBuffer(), user1.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const startStream = (startTime: number, endTime: number) =>
+      program.methods
+        .startStream(amount, new anchor.BN(startTime), new anchor.BN(endTime))
+        .accounts({
+          stream: streamPda,
+          senderAccount: user2AccountPda,
+          recipientAccount: user1AccountPda,
+          sender: user2.publicKey,
+          config: configPda,
+          senderBadge: null,
+          senderBadgeTokenAccount: null,
+          mint: null,
+          vault: null,
+          senderTokenAccount: null,
+          tokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+    // Streams can't be backdated
+    const now = await chainTime();
+    try {
+      await startStream(now - 60, now + 3600);
+      assert.fail("Streams starting in the past should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidStreamWindow");
+    }
+
+    const startTime = now + 2;
+    await startStream(startTime, startTime + 3600);
+
+    // Let some of it vest
+    while ((await chainTime()) <= startTime + 1) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+
+    await program.methods
+      .withdrawStream()
+      .accounts({
+        stream: streamPda,
+        senderAccount: user2AccountPda,
+        recipientAccount: user1AccountPda,
+        recipient: user1.publicKey,
+        vault: null,
+        recipientTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user1])
+      .rpc();
+
+    const stream = await program.account.tipStream.fetch(streamPda);
+    assert.ok(stream.withdrawn.toNumber() > 0);
+    assert.ok(stream.withdrawn.toNumber() < amount.toNumber());
+
+    await program.methods
+      .cancelStream()
+      .accounts({
+        stream: streamPda,
+        senderAccount: user2AccountPda,
+        recipientAccount: user1AccountPda,
+        sender: user2.publicKey,
+        recipient: user1.publicKey,
+        vault: null,
+        senderTokenAccount: null,
+        recipientTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user2])
+      .rpc();
+
+    assert.isNull(await program.account.tipStream.fetchNullable(streamPda));
+  });
+
+  it("Cancels an escrowed tip inside its window", async () => {
+    const amount = new anchor.BN(0.02 * LAMPORTS_PER_SOL);
+    const tipId = new anchor.BN(1);
+    const [pendingTipPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("pending-tip"),
+        user1.publicKey.toBuffer(),
+        user2.publicKey.toBuffer(),
+        tipId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .sendEscrowedTip(tipId, amount, new anchor.BN(3600))
+      .accounts({
+        pendingTip: pendingTipPda,
+        senderAccount: user1AccountPda,
+        recipientAccount: user2AccountPda,
+        sender: user1.publicKey,
+        config: configPda,
+        senderBadge: null,
+        senderBadgeTokenAccount: null,
+        mint: null,
+        vault: null,
+        senderTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const pendingTip = await program.account.pendingTip.fetch(pendingTipPda);
+    assert.ok(pendingTip.recipient.equals(user2.publicKey));
+    assert.equal(pendingTip.amount.toNumber(), amount.toNumber());
+
+    await program.methods
+      .cancelTip()
+      .accounts({
+        pendingTip: pendingTipPda,
+        sender: user1.publicKey,
+        vault: null,
+        destinationTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user1])
+      .rpc();
+
+    assert.isNull(await program.account.pendingTip.fetchNullable(pendingTipPda));
+  });
+
+  it("Releases a bounty to the chosen user", async () => {
+    const amount = new anchor.BN(0.05 * LAMPORTS_PER_SOL);
+    const bountyId = new anchor.BN(1);
+    const deadline = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+    const [bountyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bounty"), user1.publicKey.toBuffer(), bountyId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .createBounty(bountyId, amount, deadline, null)
+      .accounts({
+        bounty: bountyPda,
+        creator: user1.publicKey,
+        mint: null,
+        vault: null,
+        creatorTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(user2.publicKey);
+
+    // Without an approver the creator releases the bounty
+    await program.methods
+      .releaseBounty()
+      .accounts({
+        bounty: bountyPda,
+        approver: user1.publicKey,
+        recipientAccount: user2AccountPda,
+        recipient: user2.publicKey,
+        creator: user1.publicKey,
+        vault: null,
+        destinationTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user1])
+      .rpc();
+
+    const balanceAfter = await provider.connection.getBalance(user2.publicKey);
+    assert.equal(balanceAfter - balanceBefore, amount.toNumber());
+    assert.isNull(await program.account.bounty.fetchNullable(bountyPda));
+  });
+
+  it("Settles a prize pool and pays the winner", async () => {
+    const amount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const prize = new anchor.BN(0.06 * LAMPORTS_PER_SOL);
+    const poolId = new anchor.BN(1);
+    const claimDeadline = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+    const [prizePoolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("prize-pool"), user1.publicKey.toBuffer(), poolId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .createPrizePool(poolId, amount, provider.wallet.publicKey, claimDeadline)
+      .accounts({
+        prizePool: prizePoolPda,
+        host: user1.publicKey,
+        mint: null,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    // Payouts above the pool size are rejected
+    try {
+      await program.methods
+        .settlePrizePool([{ winner: user2.publicKey, amount: amount.addn(1) }])
+        .accounts({
+          prizePool: prizePoolPda,
+          settlementAuthority: provider.wallet.publicKey,
+          host: user1.publicKey,
+          vault: null,
+          hostTokenAccount: null,
+          tokenProgram: null,
+        })
+        .rpc();
+      assert.fail("Oversized payouts should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "PayoutsExceedPool");
+    }
+
+    await program.methods
+      .settlePrizePool([{ winner: user2.publicKey, amount: prize }])
+      .accounts({
+        prizePool: prizePoolPda,
+        settlementAuthority: provider.wallet.publicKey,
+        host: user1.publicKey,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    await program.methods
+      .claimPrize()
+      .accounts({
+        prizePool: prizePoolPda,
+        winner: user2.publicKey,
+        vault: null,
+        winnerTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user2])
+      .rpc();
+
+    const pool = await program.account.prizePool.fetch(prizePoolPda);
+    assert.equal(pool.amount.toNumber(), prize.toNumber());
+    assert.equal(pool.claimedAmount.toNumber(), prize.toNumber());
+    assert.equal(pool.winners[0].claimed, true);
+  });
+
+  it("Rejects prize claims after the claim deadline", async () => {
+    const amount = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+    const poolId = new anchor.BN(2);
+    const claimDeadline = Math.floor(Date.now() / 1000) + 5;
+    const [prizePoolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("prize-pool"), user1.publicKey.toBuffer(), poolId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .createPrizePool(poolId, amount, provider.wallet.publicKey, new anchor.BN(claimDeadline))
+      .accounts({
+        prizePool: prizePoolPda,
+        host: user1.publicKey,
+        mint: null,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+    await program.methods
+      .settlePrizePool([{ winner: user2.publicKey, amount }])
+      .accounts({
+        prizePool: prizePoolPda,
+        settlementAuthority: provider.wallet.publicKey,
+        host: user1.publicKey,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    // The winner waits too long
+    while (
+      (await provider.connection.getBlockTime(await provider.connection.getSlot())) < claimDeadline
+    ) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+
+    try {
+      await program.methods
+        .claimPrize()
+        .accounts({
+          prizePool: prizePoolPda,
+          winner: user2.publicKey,
+          vault: null,
+          winnerTokenAccount: null,
+          tokenProgram: null,
+        })
+        .signers([user2])
+        .rpc();
+      assert.fail("Claims after the deadline should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "ClaimDeadlinePassed");
+    }
+
+    // The host takes the unclaimed prize back
+    await program.methods
+      .closePrizePool()
+      .accounts({
+        prizePool: prizePoolPda,
+        host: user1.publicKey,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user1])
+      .rpc();
+    assert.isNull(await program.account.prizePool.fetchNullable(prizePoolPda));
+  });
+
+  it("Draws a commit-reveal giveaway", async () => {
+    const giveawayId = new anchor.BN(1);
+    const prize = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+    const secret = randomBytes(32);
+    const commitment = [...createHash("sha256").update(secret).digest()];
+    const drawSlot = (await provider.connection.getSlot()) + 10;
+    const claimDeadline = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+    const [giveawayPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("giveaway"), user1.publicKey.toBuffer(), giveawayId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .createGiveaway(giveawayId, commitment, prize, 1, new anchor.BN(drawSlot), claimDeadline)
+      .accounts({
+        giveaway: giveawayPda,
+        host: user1.publicKey,
+        mint: null,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    await program.methods
+      .enterGiveaway()
+      .accounts({ giveaway: giveawayPda, entrant: user2.publicKey })
+      .signers([user2])
+      .rpc();
+
+    while ((await provider.connection.getSlot()) <= drawSlot) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+
+    await program.methods
+      .revealGiveaway([...secret])
+      .accounts({
+        giveaway: giveawayPda,
+        host: user1.publicKey,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+      })
+      .signers([user1])
+      .rpc();
+
+    // The only entrant must be the winner
+    const giveaway = await program.account.giveaway.fetch(giveawayPda);
+    assert.equal(giveaway.revealed, true);
+    assert.ok(giveaway.winners[0].winner.equals(user2.publicKey));
+
+    await program.methods
+      .claimGiveawayPrize()
+      .accounts({
+        giveaway: giveawayPda,
+        winner: user2.publicKey,
+        vault: null,
+        winnerTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user2])
+      .rpc();
+  });
+
+  it("Forfeits an unrevealed giveaway to its entrants", async () => {
+    const giveawayId = new anchor.BN(2);
+    const prize = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+    const secret = randomBytes(32);
+    const commitment = [...createHash("sha256").update(secret).digest()];
+    const drawSlot = (await provider.connection.getSlot()) + 10;
+    const claimDeadline = Math.floor(Date.now() / 1000) + 5;
+    const [giveawayPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("giveaway"), user1.publicKey.toBuffer(), giveawayId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .createGiveaway(giveawayId, commitment, prize, 2, new anchor.BN(drawSlot), new anchor.BN(claimDeadline))
+      .accounts({
+        giveaway: giveawayPda,
+        host: user1.publicKey,
+        mint: null,
+        vault: null,
+        hostTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    await program.methods
+      .enterGiveaway()
+      .accounts({ giveaway: giveawayPda, entrant: user2.publicKey })
+      .signers([user2])
+      .rpc();
+
+    // The host withholds the reveal until the claim deadline passes
+    while (
+      (await provider.connection.getBlockTime(await provider.connection.getSlot())) < claimDeadline
+    ) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+
+    const closeGiveaway = () =>
+      program.methods
+        .closeGiveaway()
+        .accounts({
+          giveaway: giveawayPda,
+          host: user1.publicKey,
+          vault: null,
+          hostTokenAccount: null,
+          tokenProgram: null,
+        })
+        .signers([user1])
+        .rpc();
+
+    // The prize now belongs to the entrants, so the host can't close it out
+    try {
+      await closeGiveaway();
+      assert.fail("Host closed a forfeited giveaway");
+    } catch (err) {
+      assert.include(err.toString(), "PrizeForfeited");
+    }
+
+    const balanceBefore = await provider.connection.getBalance(user2.publicKey);
+    await program.methods
+      .claimForfeitedGiveaway()
+      .accounts({
+        giveaway: giveawayPda,
+        entrant: user2.publicKey,
+        vault: null,
+        entrantTokenAccount: null,
+        tokenProgram: null,
+      })
+      .signers([user2])
+      .rpc();
+
+    // The only entrant takes both prizes; the fee payer is the provider wallet
+    const balanceAfter = await provider.connection.getBalance(user2.publicKey);
+    assert.equal(balanceAfter - balanceBefore, 2 * prize.toNumber());
+
+    await closeGiveaway();
+    assert.isNull(await program.account.giveaway.fetchNullable(giveawayPda));
+  });
+
+  it("Matches a SOL tip from a sponsor pool", async () => {
+    const sponsor = provider.wallet.publicKey;
+    const poolId = new anchor.BN(1);
+    const cap = new anchor.BN(0.05 * LAMPORTS_PER_SOL);
+    const tipAmount = new anchor.BN(0.02 * LAMPORTS_PER_SOL);
+    const senderCap = new anchor.BN(0.03 * LAMPORTS_PER_SOL);
+    const expiry = new anchor.BN(Math.floor(Date.now() / 1000) + 7 * 24 * 60 * 60);
+    const [matchingPoolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("matching-pool"), sponsor.toBuffer(), poolId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [matchRecordPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("match-record"), matchingPoolPda.toBuffer(), user1.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // Match every tip to user2 one-for-one, up to senderCap per sender
+    await program.methods
+      .createMatchingPool(poolId, user2.publicKey, 10_000, cap, senderCap, expiry)
+      .accounts({
+        matchingPool: matchingPoolPda,
+        sponsor,
+        mint: null,
+        vault: null,
+        sponsorTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .openMatchRecord()
+      .accounts({
+        matchRecord: matchRecordPda,
+        matchingPool: matchingPoolPda,
+        sender: user1.publicKey,
+        payer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const tip = () =>
+      program.methods
+        .tipSol(tipAmount)
+        .accounts({
+          senderAccount: user1AccountPda,
+          recipientAccount: user2AccountPda,
+          sender: user1.publicKey,
+          recipient: user2.publicKey,
+          matchingPool: matchingPoolPda,
+          matchRecord: matchRecordPda,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: null,
+          senderBadgeTokenAccount: null,
+          recipientBadge: null,
+          tipPair: null,
+          guildTreasury: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+    const before = await program.account.userAccount.fetch(user2AccountPda);
+    await tip();
+
+    const after = await program.account.userAccount.fetch(user2AccountPda);
+    assert.equal(
+      after.totalReceived.toNumber() - before.totalReceived.toNumber(),
+      afterFee(tipAmount) + tipAmount.toNumber()
+    );
+
+    const pool = await program.account.matchingPool.fetch(matchingPoolPda);
+    assert.equal(pool.matched.toNumber(), tipAmount.toNumber());
+
+    // Repeat tips only draw what is left of the sender's cap, then nothing,
+    // even though the pool itself still has funds
+    await tip();
+    await tip();
+
+    const record = await program.account.matchRecord.fetch(matchRecordPda);
+    assert.equal(record.matched.toNumber(), senderCap.toNumber());
+    const capped = await program.account.matchingPool.fetch(matchingPoolPda);
+    assert.equal(capped.matched.toNumber(), senderCap.toNumber());
+    assert.isAbove(cap.toNumber() - capped.matched.toNumber(), 0);
+
+    // Tips the pool doesn't apply to go through without a match record
+    await program.methods
+      .tipSol(tipAmount)
+      .accounts({
+        senderAccount: user2AccountPda,
+        recipientAccount: user1AccountPda,
+        sender: user2.publicKey,
+        recipient: user1.publicKey,
+        matchingPool: matchingPoolPda,
+        matchRecord: null,
+        config: configPda,
+        treasury,
+        referrerAccount: null,
+        senderBadge: null,
+        senderBadgeTokenAccount: null,
+        recipientBadge: null,
+        tipPair: null,
+        guildTreasury: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user2])
+      .rpc();
+    const unmatched = await program.account.matchingPool.fetch(matchingPoolPda);
+    assert.equal(unmatched.matched.toNumber(), senderCap.toNumber());
+
+    // The recipient wallet has to belong to the recipient account
+    try {
+      await program.methods
+        .tipSol(tipAmount)
+        .accounts({
+          senderAccount: user1AccountPda,
+          recipientAccount: user2AccountPda,
+          sender: user1.publicKey,
+          recipient: user1.publicKey,
+          matchingPool: null,
+          matchRecord: null,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: null,
+          senderBadgeTokenAccount: null,
+          recipientBadge: null,
+          tipPair: null,
+          guildTreasury: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+      assert.fail("Tip went to a wallet other than the recipient account's");
+    } catch (err) {
+      assert.include(err.toString(), "AccountMismatch");
+    }
+  });
+
+  it("Shares the protocol fee with a referrer", async () => {
+    const user3 = Keypair.generate();
+    const discordId3 = "discord_user_789";
+    const [user3AccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), Buffer.from(discordId3)],
+      program.programId
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(user3.publicKey, LAMPORTS_PER_SOL)
+    );
+
+    // user1 referred user3
+    await program.methods
+      .initializeUser(discordId3)
+      .accounts({
+        userAccount: user3AccountPda,
+        authority: user3.publicKey,
+        referrerAccount: user1AccountPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user3])
+      .rpc();
+
+    const tipAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const before = await program.account.userAccount.fetch(user2AccountPda);
+
+    await program.methods
+      .tipSol(tipAmount)
+      .accounts({
+        senderAccount: user3AccountPda,
+        recipientAccount: user2AccountPda,
+        sender: user3.publicKey,
+        recipient: user2.publicKey,
+        matchingPool: null,
+        matchRecord: null,
+        config: configPda,
+        treasury,
+        referrerAccount: user1AccountPda,
+        senderBadge: null,
+        senderBadgeTokenAccount: null,
+        recipientBadge: null,
+        tipPair: null,
+        guildTreasury: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user3])
+      .rpc();
+
+    // A fifth of the fee goes to the referrer
+    const fee = tipAmount.toNumber() - afterFee(tipAmount);
+    const after = await program.account.userAccount.fetch(user2AccountPda);
+    assert.equal(
+      after.totalReceived.toNumber() - before.totalReceived.toNumber(),
+      tipAmount.toNumber() - fee
+    );
+
+    const referrer = await program.account.userAccount.fetch(user1AccountPda);
+    assert.equal(referrer.referralEarnings.toNumber(), fee / 5);
+
+    await program.methods
+      .withdrawReferralEarnings()
+      .accounts({ userAccount: user1AccountPda, authority: user1.publicKey })
+      .signers([user1])
+      .rpc();
+    const withdrawn = await program.account.userAccount.fetch(user1AccountPda);
+    assert.equal(withdrawn.referralEarnings.toNumber(), 0);
+
+    // Lamports sent straight to the PDA aren't referral earnings
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: user1AccountPda,
+          lamports: 1_000_000,
+        })
+      )
+    );
+    try {
+      await program.methods
+        .withdrawReferralEarnings()
+        .accounts({ userAccount: user1AccountPda, authority: user1.publicKey })
+        .signers([user1])
+        .rpc();
+      assert.fail("Withdrew lamports that weren't referral earnings");
+    } catch (err) {
+      assert.include(err.toString(), "NothingToWithdraw");
+    }
+  });
+
+  it("Lets a guild spender tip within their allowance", async () => {
+    const guildId = "guild_42";
+    const admin = provider.wallet.publicKey;
+
+    // A fresh spender, so earlier tips don't count toward their rate limit
+    const spender = Keypair.generate();
+    const [spenderAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), Buffer.from("discord_guild_spender")],
+      program.programId
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(spender.publicKey, LAMPORTS_PER_SOL)
+    );
+    await program.methods
+      .initializeUser("discord_guild_spender")
+      .accounts({
+        userAccount: spenderAccountPda,
+        authority: spender.publicKey,
+        referrerAccount: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([spender])
+      .rpc();
+    const [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guild-treasury"), Buffer.from(guildId)],
+      program.programId
+    );
+
+    await program.methods
+      .createGuildTreasury(guildId, new anchor.BN(24 * 60 * 60))
+      .accounts({
+        treasury: treasuryPda,
+        creator: admin,
+        mint: null,
+        vault: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .fundGuildTreasury(new anchor.BN(0.2 * LAMPORTS_PER_SOL))
+      .accounts({
+        treasury: treasuryPda,
+        funder: admin,
+        vault: null,
+        funderTokenAccount: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const allowance = new anchor.BN(0.05 * LAMPORTS_PER_SOL);
+    await program.methods
+      .setGuildSpender(spender.publicKey, allowance)
+      .accounts({ treasury: treasuryPda, admin })
+      .rpc();
+
+    // Spenders tip through the normal tip instruction, paid by the treasury
+    const tip = (amount: anchor.BN) =>
+      program.methods
+        .tipSol(amount)
+        .accounts({
+          senderAccount: spenderAccountPda,
+          recipientAccount: user2AccountPda,
+          sender: spender.publicKey,
+          recipient: user2.publicKey,
+          matchingPool: null,
+          matchRecord: null,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: null,
+          senderBadgeTokenAccount: null,
+          recipientBadge: null,
+          tipPair: null,
+          guildTreasury: treasuryPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([spender])
+        .rpc();
+
+    const spenderBefore = await provider.connection.getBalance(spender.publicKey);
+    const recipientBefore = await provider.connection.getBalance(user2.publicKey);
+    const tipAmount = new anchor.BN(0.03 * LAMPORTS_PER_SOL);
+    await tip(tipAmount);
+
+    assert.equal(await provider.connection.getBalance(spender.publicKey), spenderBefore);
+    assert.equal(
+      (await provider.connection.getBalance(user2.publicKey)) - recipientBefore,
+      afterFee(tipAmount)
+    );
+    const guildTreasury = await program.account.guildTreasury.fetch(treasuryPda);
+    assert.equal(guildTreasury.spenders[0].spent.toNumber(), 0.03 * LAMPORTS_PER_SOL);
+
+    // A second spend would go over the allowance for this period
+    try {
+      await tip(new anchor.BN(0.03 * LAMPORTS_PER_SOL));
+      assert.fail("Spends over the allowance should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AllowanceExceeded");
+    }
+
+    // Only admins can withdraw
+    const withdraw = (wallet: PublicKey) =>
+      program.methods
+        .withdrawGuildTreasury(new anchor.BN(0.1 * LAMPORTS_PER_SOL))
+        .accounts({
+          treasury: treasuryPda,
+          admin: wallet,
+          vault: null,
+          destinationTokenAccount: null,
+          tokenProgram: null,
+        });
+    try {
+      await withdraw(spender.publicKey).signers([spender]).rpc();
+      assert.fail("A spender withdrew from the treasury");
+    } catch (err) {
+      assert.include(err.toString(), "Unauthorized");
+    }
+
+    const treasuryBefore = await provider.connection.getBalance(treasuryPda);
+    await withdraw(admin).rpc();
+    assert.equal(
+      treasuryBefore - (await provider.connection.getBalance(treasuryPda)),
+      0.1 * LAMPORTS_PER_SOL
+    );
+  });
+
+  it("Queues admin config changes behind a timelock", async () => {
+    const admin = provider.wallet.publicKey;
+    const params = {
+      treasury,
+      feeBps: 200,
+      referralShareBps: 2_000,
+      referralPeriod: new anchor.BN(90 * 24 * 60 * 60),
+      largeAirdropThreshold: new anchor.BN(0),
+      paused: false,
+      minSolTip: new anchor.BN(MIN_SOL_TIP),
+      minTokenTips: [],
+      rateLimitTips: RATE_LIMIT_TIPS,
+      rateLimitSlots: new anchor.BN(RATE_LIMIT_SLOTS),
+      minScore: new anchor.BN(0),
+      maxScore: new anchor.BN(MAX_SCORE),
+      tipReputation: new anchor.BN(TIP_REPUTATION),
+      tipReputationDailyCap: new anchor.BN(TIP_REPUTATION_DAILY_CAP),
+      badgeCooldown: new anchor.BN(BADGE_COOLDOWN),
+      neutralScore: new anchor.BN(0),
+      scoreHalfLife: new anchor.BN(SCORE_HALF_LIFE),
+      airdropMinScore: new anchor.BN(0),
+      largeTipAmount: new anchor.BN(LARGE_TIP),
+      largeTipMinScore: new anchor.BN(LARGE_TIP_MIN_SCORE),
+      tierThresholds: TIER_THRESHOLDS.map((t) => new anchor.BN(t)),
+    };
+
+    await program.methods
+      .queueConfigChange(params)
+      .accounts({ config: configPda, admin })
+      .rpc();
+
+    let config = await program.account.config.fetch(configPda);
+    assert.equal(config.feeBps, FEE_BPS);
+    assert.equal(config.pendingChange.params.feeBps, 200);
+
+    try {
+      await program.methods.applyConfigChange().accounts({ config: configPda }).rpc();
+      assert.fail("Queued changes should not apply before their delay");
+    } catch (err) {
+      assert.include(err.toString(), "ConfigChangeNotReady");
+    }
+
+    await program.methods
+      .cancelConfigChange()
+      .accounts({ config: configPda, admin })
+      .rpc();
+
+    config = await program.account.config.fetch(configPda);
+    assert.isNull(config.pendingChange);
+  });
+
+  it("Changes the config through an M-of-N proposal", async () => {
+    const admin = provider.wallet.publicKey;
+    const [multisigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig")],
+      program.programId
+    );
+
+    // The admin hands the config over to a 2-of-2 signer set
+    await program.methods
+      .initializeMultisig([admin, user1.publicKey], 2, new anchor.BN(24 * 60 * 60))
+      .accounts({
+        multisig: multisigPda,
+        config: configPda,
+        admin,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const multisig = await program.account.multisig.fetch(multisigPda);
+    const [proposalPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), multisigPda.toBuffer(), multisig.proposalCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const params = {
+      treasury,
+      feeBps: FEE_BPS,
+      referralShareBps: 2_000,
+      referralPeriod: new anchor.BN(90 * 24 * 60 * 60),
+      largeAirdropThreshold: new anchor.BN(5 * LAMPORTS_PER_SOL),
+      paused: false,
+      minSolTip: new anchor.BN(MIN_SOL_TIP),
+      minTokenTips: [],
+      rateLimitTips: RATE_LIMIT_TIPS,
+      rateLimitSlots: new anchor.BN(RATE_LIMIT_SLOTS),
+      minScore: new anchor.BN(0),
+      maxScore: new anchor.BN(MAX_SCORE),
+      tipReputation: new anchor.BN(TIP_REPUTATION),
+      tipReputationDailyCap: new anchor.BN(TIP_REPUTATION_DAILY_CAP),
+      badgeCooldown: new anchor.BN(BADGE_COOLDOWN),
+      neutralScore: new anchor.BN(0),
+      scoreHalfLife: new anchor.BN(SCORE_HALF_LIFE),
+      airdropMinScore: new anchor.BN(0),
+      largeTipAmount: new anchor.BN(LARGE_TIP),
+      largeTipMinScore: new anchor.BN(LARGE_TIP_MIN_SCORE),
+      tierThresholds: TIER_THRESHOLDS.map((t) => new anchor.BN(t)),
+    };
+
+    await program.methods
+      .createProposal({ updateConfig: { params } })
+      .accounts({
+        multisig: multisigPda,
+        proposal: proposalPda,
+        proposer: admin,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const execute = () =>
+      program.methods
+        .executeProposal()
+        .accounts({
+          multisig: multisigPda,
+          proposal: proposalPda,
+          executor: admin,
+          config: configPda,
+          destination: null,
+          treasuryTokenAccount: null,
+          destinationTokenAccount: null,
+          attesterRegistry: null,
+          tokenProgram: null,
+        })
+        .rpc();
+
+    // One approval is not enough
+    try {
+      await execute();
+      assert.fail("Proposals below the threshold should not execute");
+    } catch (err) {
+      assert.include(err.toString(), "ThresholdNotMet");
+    }
+
+    await program.methods
+      .approveProposal()
+      .accounts({ multisig: multisigPda, proposal: proposalPda, approver: user1.publicKey })
+      .signers([user1])
+      .rpc();
+    await execute();
+
+    // The change is queued behind the config timelock
+    const config = await program.account.config.fetch(configPda);
+    assert.ok(config.admin.equals(multisigPda));
+    assert.equal(
+      config.pendingChange.params.largeAirdropThreshold.toNumber(),
+      5 * LAMPORTS_PER_SOL
+    );
+  });
+
+  it("Pays out a large airdrop approved by the multisig", async () => {
+    const creator = provider.wallet.publicKey;
+    const totalAmount = new anchor.BN(LARGE_AIRDROP);
+    const recipientsCount = 5;
+    const airdropId = new anchor.BN(1);
+    const [multisigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig")],
+      program.programId
+    );
+    const [airdropPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop"), creator.toBuffer(), airdropId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const createAirdrop = (approval: PublicKey | null) =>
+      program.methods
+        .createAirdrop(airdropId, totalAmount, recipientsCount, null, new anchor.BN(0))
+        .accounts({
+          airdrop: airdropPda,
+          creator,
+          config: configPda,
+          approval,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    // Airdrops at the threshold need an approval
+    try {
+      await createAirdrop(null);
+      assert.fail("Large airdrops without an approval should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "ApprovalRequired");
+    }
+
+    const multisig = await program.account.multisig.fetch(multisigPda);
+    const [proposalPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), multisigPda.toBuffer(), multisig.proposalCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .createProposal({ createAirdrop: { creator, totalAmount, recipientsCount } })
+      .accounts({
+        multisig: multisigPda,
+        proposal: proposalPda,
+        proposer: creator,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .approveProposal()
+      .accounts({ multisig: multisigPda, proposal: proposalPda, approver: user1.publicKey })
+      .signers([user1])
+      .rpc();
+    await program.methods
+      .executeProposal()
+      .accounts({
+        multisig: multisigPda,
+        proposal: proposalPda,
+        executor: creator,
+        config: configPda,
+        destination: null,
+        treasuryTokenAccount: null,
+        destinationTokenAccount: null,
+        attesterRegistry: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    await createAirdrop(proposalPda);
+
+    const [claimPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop-claim"), airdropPda.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+    const claim = () =>
+      program.methods
+        .claimAirdrop()
+        .accounts({
+          airdrop: airdropPda,
+          claim: claimPda,
+          claimer: user2.publicKey,
+          claimerBadge: null,
+          claimerBadgeTokenAccount: null,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(user2.publicKey);
+    await claim();
+
+    // The provider wallet pays the fee, so user2 gets exactly one share less
+    // the claim record's rent
+    const claimRent = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(claimPda)).data.length
+    );
+    const balanceAfter = await provider.connection.getBalance(user2.publicKey);
+    assert.equal(balanceAfter - balanceBefore + claimRent, totalAmount.toNumber() / recipientsCount);
+
+    // One claim per wallet
+    try {
+      await claim();
+      assert.fail("A second claim from the same wallet should be rejected");
+    } catch (err) {
+      assert.include((err.logs ?? []).join("\n"), "already in use");
+    }
+
+    const airdrop = await program.account.airdrop.fetch(airdropPda);
+    assert.equal(airdrop.claimedCount, 1);
+  });
+
+  it("Lets guardians start a recovery that the owner can veto", async () => {
+    const newWallet = Keypair.generate();
+    const [recoveryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("recovery"), user2AccountPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .setGuardians([user1.publicKey, provider.wallet.publicKey], 2, new anchor.BN(24 * 60 * 60))
+      .accounts({
+        recovery: recoveryPda,
+        userAccount: user2AccountPda,
+        authority: user2.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user2])
+      .rpc();
+
+    await program.methods
+      .approveRecovery(newWallet.publicKey)
+      .accounts({ recovery: recoveryPda, userAccount: user2AccountPda, guardian: user1.publicKey })
+      .signers([user1])
+      .rpc();
+    await program.methods
+      .approveRecovery(newWallet.publicKey)
+      .accounts({
+        recovery: recoveryPda,
+        userAccount: user2AccountPda,
+        guardian: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    // Threshold met, but the delay has not passed
+    try {
+      await program.methods
+        .executeRecovery()
+        .accounts({ recovery: recoveryPda, userAccount: user2AccountPda })
+        .rpc();
+      assert.fail("Recovery should wait out its delay");
+    } catch (err) {
+      assert.include(err.toString(), "RecoveryNotReady");
+    }
+
+    await program.methods
+      .vetoRecovery()
+      .accounts({ recovery: recoveryPda, userAccount: user2AccountPda, authority: user2.publicKey })
+      .signers([user2])
+      .rpc();
+
+    const recovery = await program.account.guardianSet.fetch(recoveryPda);
+    assert.isNull(recovery.pending);
+    const user = await program.account.userAccount.fetch(user2AccountPda);
+    assert.ok(user.authority.equals(user2.publicKey));
+  });
+
+  it("Recovers past a guardian who proposed the wrong wallet", async () => {
+    const rogue = Keypair.generate();
+    const badWallet = Keypair.generate().publicKey;
+    const goodWallet = Keypair.generate().publicKey;
+    const [recoveryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("recovery"), user2AccountPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .updateGuardians(
+        [user1.publicKey, provider.wallet.publicKey, rogue.publicKey],
+        2,
+        new anchor.BN(24 * 60 * 60)
+      )
+      .accounts({ recovery: recoveryPda, userAccount: user2AccountPda, authority: user2.publicKey })
+      .signers([user2])
+      .rpc();
+
+    const approve = (guardian: Keypair | null, newAuthority: PublicKey) =>
+      program.methods
+        .approveRecovery(newAuthority)
+        .accounts({
+          recovery: recoveryPda,
+          userAccount: user2AccountPda,
+          guardian: guardian ? guardian.publicKey : provider.wallet.publicKey,
+        })
+        .signers(guardian ? [guardian] : [])
+        .rpc();
+
+    // The rogue guardian opens a recovery to the wrong wallet first
+    await approve(rogue, badWallet);
+    await approve(user1, goodWallet);
+    await approve(null, goodWallet);
+
+    let recovery = await program.account.guardianSet.fetch(recoveryPda);
+    assert.ok(recovery.pending.newAuthority.equals(goodWallet));
+    assert.isNotNull(recovery.pending.executableAt);
+
+    // The locked-in wallet can't be swapped out
+    try {
+      await approve(rogue, badWallet);
+      assert.fail("Approvals for another wallet should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "RecoveryMismatch");
+    }
+
+    await program.methods
+      .vetoRecovery()
+      .accounts({ recovery: recoveryPda, userAccount: user2AccountPda, authority: user2.publicKey })
+      .signers([user2])
+      .rpc();
+    recovery = await program.account.guardianSet.fetch(recoveryPda);
+    assert.isNull(recovery.pending);
+  });
+
+  it("Rejects dust tips and rate limits busy senders", async () => {
+    const spammer = Keypair.generate();
+    const discordId = "discord_user_spam";
+    const [spammerAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), Buffer.from(discordId)],
+      program.programId
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(spammer.publicKey, LAMPORTS_PER_SOL)
+    );
+    await program.methods
+      .initializeUser(discordId)
+      .accounts({
+        userAccount: spammerAccountPda,
+        authority: spammer.publicKey,
+        referrerAccount: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([spammer])
+      .rpc();
+
+    const tip = (amount: number) =>
+      program.methods
+        .tipSol(new anchor.BN(amount))
+        .accounts({
+          senderAccount: spammerAccountPda,
+          recipientAccount: user2AccountPda,
+          sender: spammer.publicKey,
+          recipient: user2.publicKey,
+          matchingPool: null,
+          matchRecord: null,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: null,
+          senderBadgeTokenAccount: null,
+          recipientBadge: null,
+          tipPair: null,
+          guildTreasury: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([spammer])
+        .rpc();
+
+    try {
+      await tip(1);
+      assert.fail("Dust tips should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TipBelowMinimum");
+    }
+
+    for (let i = 0; i < RATE_LIMIT_TIPS; i++) {
+      await tip(MIN_SOL_TIP);
+    }
+    try {
+      await tip(MIN_SOL_TIP);
+      assert.fail("Tips over the rate limit should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TipRateLimited");
+    }
+
+    const spammerAccount = await program.account.userAccount.fetch(spammerAccountPda);
+    assert.equal(spammerAccount.tipCount.toNumber(), RATE_LIMIT_TIPS);
+
+    // Tipping through someone else's user account doesn't dodge the limit
+    try {
+      await program.methods
+        .tipSol(new anchor.BN(MIN_SOL_TIP))
+        .accounts({
+          senderAccount: user1AccountPda,
+          recipientAccount: user2AccountPda,
+          sender: spammer.publicKey,
+          recipient: user2.publicKey,
+          matchingPool: null,
+          matchRecord: null,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: null,
+          senderBadgeTokenAccount: null,
+          recipientBadge: null,
+          tipPair: null,
+          guildTreasury: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([spammer])
+        .rpc();
+      assert.fail("Tips from another user's account should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AccountMismatch");
+    }
+  });
+
+  it("Claims a voucher airdrop with a bot-signed voucher", async () => {
+    const creator = user1;
+    const bot = Keypair.generate();
+    const totalAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const airdropId = new anchor.BN(2);
+    const [airdropPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop"), creator.publicKey.toBuffer(), airdropId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .createAirdrop(airdropId, totalAmount, 5, bot.publicKey, new anchor.BN(0))
+      .accounts({
+        airdrop: airdropPda,
+        creator: creator.publicKey,
+        config: configPda,
+        approval: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const amount = new anchor.BN(0.02 * LAMPORTS_PER_SOL);
+    const expiry = new anchor.BN(Math.floor(Date.now() / 1000) + 60 * 60);
+    const message = Buffer.concat([
+      airdropPda.toBuffer(),
+      user2.publicKey.toBuffer(),
+      amount.toArrayLike(Buffer, "le", 8),
+      expiry.toArrayLike(Buffer, "le", 8),
+    ]);
+    const [claimPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop-claim"), airdropPda.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .claimAirdropVoucher(amount, expiry)
+      .accounts({
+        airdrop: airdropPda,
+        claim: claimPda,
+        claimer: user2.publicKey,
+        claimerBadge: null,
+        claimerBadgeTokenAccount: null,
+        config: configPda,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([
+        Ed25519Program.createInstructionWithPrivateKey({ privateKey: bot.secretKey, message }),
+      ])
+      .signers([user2])
+      .rpc();
+
+    const claim = await program.account.airdropClaim.fetch(claimPda);
+    assert.equal(claim.amount.toNumber(), amount.toNumber());
+    const airdrop = await program.account.airdrop.fetch(airdropPda);
+    assert.equal(airdrop.claimedAmount.toNumber(), amount.toNumber());
+  });
+
+  it("Mints a trust badge through the program's badge authority", async () => {
+    const recipient = Keypair.generate();
+    const {
+      badge: badgePda,
+      badgeMint,
+      tokenAccount: recipientTokenAccount,
+    } = await mintBadge(recipient, 500);
+
+    const balance = await provider.connection.getTokenAccountBalance(recipientTokenAccount);
+    assert.equal(balance.value.amount, "1");
+
+    // Soulbound: the holding is frozen at mint time
+    const holding = await provider.connection.getParsedAccountInfo(recipientTokenAccount);
+    assert.equal((holding.value.data as any).parsed.info.state, "frozen");
+
+    // ...so the holder can't move it to another wallet
+    const otherTokenAccount = await createTokenAccount(badgeMint, user1.publicKey);
+    try {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          // Transfer
+          new TransactionInstruction({
+            programId: TOKEN_PROGRAM_ID,
+            keys: [
+              { pubkey: recipientTokenAccount, isSigner: false, isWritable: true },
+              { pubkey: otherTokenAccount, isSigner: false, isWritable: true },
+              { pubkey: recipient.publicKey, isSigner: true, isWritable: false },
+            ],
+            data: Buffer.concat([Buffer.from([3]), new anchor.BN(1).toArrayLike(Buffer, "le", 8)]),
+          })
+        ),
+        [recipient]
+      );
+      assert.fail("A soulbound badge was transferred");
+    } catch (err) {
+      assert.include((err.logs ?? []).join("\n"), "Account is frozen");
+    }
+    assert.equal(await tokenBalance(recipientTokenAccount), 1);
+    assert.equal(await tokenBalance(otherTokenAccount), 0);
+
+    await program.methods
+      .updateScore(new anchor.BN(-200), { reject: {} }, 1)
+      .accounts({
+        badge: badgePda,
+        registry: attesterRegistry,
+        config: configPda,
+        attester: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const badge = await program.account.trustBadge.fetch(badgePda);
+    assert.ok(badge.owner.equals(recipient.publicKey));
+    assert.ok(badge.mint.equals(badgeMint));
+    assert.equal(badge.reputationScore.toNumber(), 300);
+
+    // Rejecting mode refuses to leave the configured range
+    try {
+      await program.methods
+        .updateScore(new anchor.BN(-1_000), { reject: {} }, 2)
+        .accounts({
+          badge: badgePda,
+          registry: attesterRegistry,
+          config: configPda,
+          attester: provider.wallet.publicKey,
+        })
+        .rpc();
+      assert.fail("Should have rejected a score below the minimum");
+    } catch (err) {
+      assert.include(err.toString(), "ScoreOutOfRange");
+    }
+
+    // Saturating mode stops at the bound instead
+    await program.methods
+      .updateScore(new anchor.BN(-1_000), { saturate: {} }, 2)
+      .accounts({
+        badge: badgePda,
+        registry: attesterRegistry,
+        config: configPda,
+        attester: provider.wallet.publicKey,
+      })
+      .rpc();
+    assert.equal((await program.account.trustBadge.fetch(badgePda)).reputationScore.toNumber(), 0);
+
+    try {
+      await program.methods
+        .updateScore(new anchor.BN(10), { saturate: {} }, 1)
+        .accounts({
+          badge: badgePda,
+          registry: attesterRegistry,
+          config: configPda,
+          attester: user1.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+      assert.fail("Should have rejected an unregistered attester");
+    } catch (err) {
+      assert.include(err.toString(), "NotAnAttester");
+    }
+
+    // Scores are read from return data, without sending a transaction
+    const score = await program.methods
+      .getScore()
+      .accounts({ badge: badgePda, badgeTokenAccount: recipientTokenAccount, config: configPda })
+      .view();
+    assert.ok(score.owner.equals(recipient.publicKey));
+    assert.ok(score.mint.equals(badgeMint));
+    assert.equal(score.reputationScore.toNumber(), 0);
+    assert.deepEqual(score.tier, { unranked: {} });
+  });
+
+  it("Accrues badge reputation from tips with diminishing returns per pair", async () => {
+    const alice = Keypair.generate();
+    const bob = Keypair.generate();
+    const users = [
+      { wallet: alice, discordId: "discord_rep_alice" },
+      { wallet: bob, discordId: "discord_rep_bob" },
+    ].map(({ wallet, discordId }) => ({
+      wallet,
+      discordId,
+      account: PublicKey.findProgramAddressSync(
+        [Buffer.from("user"), Buffer.from(discordId)],
+        program.programId
+      )[0],
+      badge: PublicKey.findProgramAddressSync(
+        [Buffer.from("trust-badge"), wallet.publicKey.toBuffer()],
+        program.programId
+      )[0],
+    }));
+    for (const user of users) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(user.wallet.publicKey, LAMPORTS_PER_SOL)
+      );
+      await program.methods
+        .initializeUser(user.discordId)
+        .accounts({
+          userAccount: user.account,
+          authority: user.wallet.publicKey,
+          referrerAccount: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user.wallet])
+        .rpc();
+      await mintBadge(user.wallet, 100);
+    }
+
+    const [walletA, walletB] = [alice.publicKey, bob.publicKey].sort((a, b) =>
+      Buffer.compare(a.toBuffer(), b.toBuffer())
+    );
+    const [tipPairPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tip-pair"), walletA.toBuffer(), walletB.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .openTipPair()
+      .accounts({
+        tipPair: tipPairPda,
+        walletA,
+        walletB,
+        payer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const tip = (from: typeof users[0], to: typeof users[0]) =>
+      program.methods
+        .tipSol(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({
+          senderAccount: from.account,
+          recipientAccount: to.account,
+          sender: from.wallet.publicKey,
+          recipient: to.wallet.publicKey,
+          matchingPool: null,
+          matchRecord: null,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: from.badge,
+          senderBadgeTokenAccount: null,
+          recipientBadge: to.badge,
+          tipPair: tipPairPda,
+          guildTreasury: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([from.wallet])
+        .rpc();
+
+    // Tipping back and forth halves the reward every time
+    await tip(users[0], users[1]);
+    await tip(users[1], users[0]);
+    await tip(users[0], users[1]);
+
+    const expected = 100 + TIP_REPUTATION + TIP_REPUTATION / 2 + Math.floor(TIP_REPUTATION / 4);
+    for (const user of users) {
+      const badge = await program.account.trustBadge.fetch(user.badge);
+      assert.equal(badge.reputationScore.toNumber(), expected);
+    }
+    const tipPair = await program.account.tipPair.fetch(tipPairPda);
+    assert.equal(tipPair.tips, 3);
+  });
+
+  it("Revokes a badge, burning it and blocking a re-mint during the cooldown", async () => {
+    const fraudster = Keypair.generate();
+    const { badge, revocation, badgeMint, tokenAccount } = await mintBadge(fraudster, 200);
+    const revoke = (authority: Keypair) =>
+      program.methods
+        .revokeBadge(7)
+        .accounts({
+          badge,
+          badgeMint,
+          ownerTokenAccount: tokenAccount,
+          mintAuthority: badgeAuthority,
+          revocation,
+          registry: attesterRegistry,
+          config: configPda,
+          authority: authority.publicKey,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    // Wallets that aren't attesters can't revoke
+    try {
+      await revoke(user1);
+      assert.fail("A non-attester revoked a badge");
+    } catch (err) {
+      assert.include(err.toString(), "Unauthorized");
+    }
+
+    // Any registered attester can, not only the one that minted the badge
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: moderator.publicKey,
+          lamports: 0.1 * LAMPORTS_PER_SOL,
+        })
+      )
+    );
+    await revoke(moderator);
+
+    const balance = await provider.connection.getTokenAccountBalance(tokenAccount);
+    assert.equal(balance.value.amount, "0");
+    assert.isNull(await provider.connection.getAccountInfo(badge));
+
+    const record = await program.account.badgeRevocation.fetch(revocation);
+    assert.ok(record.owner.equals(fraudster.publicKey));
+    assert.equal(record.reason, 7);
+    assert.equal(record.count, 1);
+    assert.ok(record.revokedBy.equals(moderator.publicKey));
+    assert.equal(record.reinstateAt.toNumber() - record.revokedAt.toNumber(), BADGE_COOLDOWN);
+
+    try {
+      await mintBadge(fraudster, 200);
+      assert.fail("Should not re-mint during the cooldown");
+    } catch (err) {
+      assert.include(err.toString(), "BadgeRevoked");
+    }
+  });
+
+  it("Aggregates weighted contributions from several attesters", async () => {
+    const holder = Keypair.generate();
+    const { badge } = await mintBadge(holder, 400);
+
+    // The moderator (weight 3) adds its own contribution of 800
+    await program.methods
+      .updateScore(new anchor.BN(800), { reject: {} }, 3)
+      .accounts({
+        badge,
+        registry: attesterRegistry,
+        config: configPda,
+        attester: moderator.publicKey,
+      })
+      .signers([moderator])
+      .rpc();
+
+    const account = await program.account.trustBadge.fetch(badge);
+    assert.equal(account.contributions.length, 2);
+    assert.equal(account.contributions[0].score.toNumber(), 400);
+    assert.equal(account.contributions[1].score.toNumber(), 800);
+    // (400 * 1 + 800 * 3) / 4
+    assert.equal(account.reputationScore.toNumber(), 700);
+  });
+
+  it("Requires a trusted badge for gated airdrop claims and large tips", async () => {
+    const creator = user1;
+    const airdropId = new anchor.BN(3);
+    const [airdropPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop"), creator.publicKey.toBuffer(), airdropId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .createAirdrop(airdropId, new anchor.BN(0.1 * LAMPORTS_PER_SOL), 5, null, new anchor.BN(300))
+      .accounts({
+        airdrop: airdropPda,
+        creator: creator.publicKey,
+        config: configPda,
+        approval: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const airdrop = await program.account.airdrop.fetch(airdropPda);
+    assert.equal(airdrop.minClaimScore.toNumber(), 300);
+
+    // A fresh wallet without a badge can't claim
+    try {
+      await program.methods
+        .claimAirdrop()
+        .accounts({
+          airdrop: airdropPda,
+          claim: PublicKey.findProgramAddressSync(
+            [Buffer.from("airdrop-claim"), airdropPda.toBuffer(), user2.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          claimer: user2.publicKey,
+          claimerBadge: null,
+          claimerBadgeTokenAccount: null,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+      assert.fail("Claims without a badge should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TrustScoreTooLow");
+    }
+
+    const whale = Keypair.generate();
+    const discordId = "discord_whale";
+    const [whaleAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user"), Buffer.from(discordId)],
+      program.programId
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(whale.publicKey, 3 * LAMPORTS_PER_SOL)
+    );
+    await program.methods
+      .initializeUser(discordId)
+      .accounts({
+        userAccount: whaleAccount,
+        authority: whale.publicKey,
+        referrerAccount: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([whale])
+      .rpc();
+
+    const tip = (badge: { badge: PublicKey; tokenAccount: PublicKey } | null) =>
+      program.methods
+        .tipSol(new anchor.BN(LARGE_TIP))
+        .accounts({
+          senderAccount: whaleAccount,
+          recipientAccount: user2AccountPda,
+          sender: whale.publicKey,
+          recipient: user2.publicKey,
+          matchingPool: null,
+          matchRecord: null,
+          config: configPda,
+          treasury,
+          referrerAccount: null,
+          senderBadge: badge?.badge ?? null,
+          senderBadgeTokenAccount: badge?.tokenAccount ?? null,
+          recipientBadge: null,
+          tipPair: null,
+          guildTreasury: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([whale])
+        .rpc();
+
+    try {
+      await tip(null);
+      assert.fail("Large tips without a badge should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TrustScoreTooLow");
+    }
+
+    // The badge only counts alongside the owner's frozen badge holding
+    const whaleBadge = await mintBadge(whale, LARGE_TIP_MIN_SCORE);
+    const otherMint = await createMint(0);
+    try {
+      await tip({
+        badge: whaleBadge.badge,
+        tokenAccount: await createTokenAccount(otherMint, whale.publicKey, 1),
+      });
+      assert.fail("A badge passed the gate without its holding");
+    } catch (err) {
+      assert.include(err.toString(), "BadgeHolderMismatch");
+    }
+
+    // The badge only clears the gate: without a tip pair nothing accrues
+    await tip(whaleBadge);
+    const { tipScore } = await program.account.trustBadge.fetch(whaleBadge.badge);
+    assert.equal(tipScore.toNumber(), 0);
+  });
+
+  it("Moves a badge up a tier when an attester raises its score", async () => {
+    const member = Keypair.generate();
+    const { badge } = await mintBadge(member, 400);
+
+    let account = await program.account.trustBadge.fetch(badge);
+    assert.deepEqual(account.tier, { bronze: {} });
+
+    let tierChanged = null;
+    const listener = program.addEventListener("TierChanged", (event) => {
+      tierChanged = event;
+    });
+    await program.methods
+      .updateScore(new anchor.BN(200), { reject: {} }, 4)
+      .accounts({
+        badge,
+        registry: attesterRegistry,
+        config: configPda,
+        attester: provider.wallet.publicKey,
+      })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 1_000));
+    await program.removeEventListener(listener);
+
+    account = await program.account.trustBadge.fetch(badge);
+    assert.equal(account.reputationScore.toNumber(), 600);
+    assert.deepEqual(account.tier, { silver: {} });
+    assert.ok(tierChanged);
+    assert.deepEqual(tierChanged.previousTier, { bronze: {} });
+    assert.deepEqual(tierChanged.tier, { silver: {} });
+  });
 });